@@ -25,7 +25,7 @@ pub const DIRECTIONS: [Direction; 6] = [
 pub const WIDTH: usize = 34;
 pub const HEIGHT: usize = 26;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Hash, Serialize, Deserialize)]
 pub struct Grid<T>(Box<[T]>);
 
 /// A 2d index of a hexagonal grid.
@@ -368,4 +368,19 @@ mod tests {
         let mut positions = inner_positions();
         assert!(positions.all(|pos| !on_outer_edge(pos)));
     }
+
+    #[test]
+    fn test_line_is_connected() {
+        let start = Pos { x: 0, y: 0 };
+        for &(x, y) in &[(5, 0), (3, 2), (-4, 7), (1, -6), (0, 0)] {
+            let end = start + Displacement { x, y };
+            let positions: Vec<Pos> = start.to(end).into_iter().collect();
+            assert_eq!(positions[0], start);
+            assert_eq!(*positions.last().unwrap(), end);
+            assert_eq!(positions.len() as u32, start.distance(end) + 1);
+            for pair in positions.windows(2) {
+                assert_eq!(pair[0].distance(pair[1]), 1);
+            }
+        }
+    }
 }
@@ -4,6 +4,14 @@ use fov::calc_fov;
 use grid::{Grid, Pos};
 use rand::Rng;
 
+/// One in this many tiles surrounded by grass grows a bush
+const BUSH_CHANCE: u32 = 8;
+/// One in this many tiles on a shore grows tall grass
+///
+/// Tall grass blocks sight and shots, so it is kept sparse enough that lakes
+/// stay open to see and shoot across.
+const TALL_GRASS_CHANCE: u32 = 8;
+
 pub(super) fn add_grass<R: Rng>(level: &mut Grid<Terrain>, rng: &mut R) {
     let positions = calc_shuffled_positions(rng);
     for &pos in &positions {
@@ -16,6 +24,30 @@ pub(super) fn add_grass<R: Rng>(level: &mut Grid<Terrain>, rng: &mut R) {
     }
 }
 
+/// Grows brownberry bushes here and there in the middle of grassy caves.
+pub(super) fn add_bushes<R: Rng>(level: &mut Grid<Terrain>, rng: &mut R) {
+    let positions = calc_shuffled_positions(rng);
+    for &pos in &positions {
+        let in_meadow = level[pos] == Terrain::ShortGrass
+            && pos.neighbors().all(|pos| level[pos] == Terrain::ShortGrass);
+        if in_meadow && rng.gen_range(0, BUSH_CHANCE) == 0 {
+            level[pos] = Terrain::Brownberry;
+        }
+    }
+}
+
+/// Grows tall grass along the shores of lakes.
+pub(super) fn add_tall_grass<R: Rng>(level: &mut Grid<Terrain>, rng: &mut R) {
+    let positions = calc_shuffled_positions(rng);
+    for &pos in &positions {
+        let on_shore = (level[pos] == Terrain::Floor || level[pos] == Terrain::ShortGrass)
+            && pos.neighbors().any(|pos| level[pos] == Terrain::Water);
+        if on_shore && rng.gen_range(0, TALL_GRASS_CHANCE) == 0 {
+            level[pos] = Terrain::TallGrass;
+        }
+    }
+}
+
 fn calc_fov_size(level: &mut Grid<Terrain>, pos: Pos) -> u32 {
     let transparent = |pos| match level[pos] {
         Terrain::Floor | Terrain::ShortGrass | Terrain::TallGrass => true,
@@ -25,3 +57,55 @@ fn calc_fov_size(level: &mut Grid<Terrain>, pos: Pos) -> u32 {
     calc_fov(pos, transparent, |_| fov_size += 1);
     fov_size
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid;
+    use level::{basic, Architect};
+    use rand::{IsaacRng, SeedableRng};
+
+    #[test]
+    fn test_bushes_grow_in_grass() {
+        let mut bushes = 0;
+        for seed in 0..10 {
+            let mut rng = IsaacRng::from_seed([seed; 32]);
+            let mut level = basic::generate(&mut rng)
+                .iter()
+                .map(|&t| Terrain::from(t))
+                .collect();
+            add_grass(&mut level, &mut rng);
+            add_bushes(&mut level, &mut rng);
+            for pos in grid::positions().filter(|&pos| level[pos] == Terrain::Brownberry) {
+                bushes += 1;
+                assert!(pos.neighbors().all(|pos| level[pos] == Terrain::ShortGrass));
+            }
+        }
+        assert!(bushes > 0);
+    }
+
+    #[test]
+    fn test_tall_grass_grows_on_shores() {
+        let (mut tall_grass, mut shore) = (0, 0);
+        for seed in 0..20 {
+            let (level, _) = Architect::new(seed).generate();
+            for pos in grid::inner_positions() {
+                let terrain = level[pos].terrain;
+                let on_shore = pos
+                    .neighbors()
+                    .any(|pos| level[pos].terrain == Terrain::Water);
+                if terrain == Terrain::TallGrass {
+                    assert!(on_shore);
+                    tall_grass += 1;
+                }
+                if on_shore && terrain.passable() {
+                    shore += 1;
+                }
+            }
+        }
+        assert!(tall_grass > 0);
+        // Most of the shore stays see-through
+        assert!(tall_grass * 4 < shore);
+    }
+}
@@ -0,0 +1,184 @@
+//! Scatter items through a level.
+//!
+//! What is found is rolled from a table whose weights change with depth, and
+//! where it is found favors the out-of-the-way places worth exploring.
+
+use super::basic::count_neighbor_groups;
+use super::tile::{Terrain, Tile};
+use prelude::*;
+use rand::Rng;
+use world::item::{Item, Potion, Scroll};
+
+/// Items placed anywhere on each level, before the ones near the exit
+const LOOT_PER_LEVEL: u32 = 4;
+/// Items always placed close to the exit of each level
+const EXIT_LOOT: u32 = 1;
+/// How close to the exit the guaranteed items are placed
+const EXIT_RADIUS: u32 = 3;
+
+/// How likely a spot is to hold an item, relative to an ordinary one
+const DEAD_END_WEIGHT: u32 = 5;
+const SHORE_WEIGHT: u32 = 3;
+
+/// Each item that can be found, the first depth it can be found at, its
+/// weight at that depth, and how much its weight grows every level deeper
+const LOOT: &[(Item, u32, u32, u32)] = &[
+    (Item::Brownberry, 1, 6, 0),
+    (Item::Potion(Potion::Healing), 1, 4, 2),
+    (Item::Potion(Potion::Fortitude), 1, 3, 2),
+    (Item::Potion(Potion::Swiftness), 2, 2, 2),
+    (Item::Scroll(Scroll::Mapping), 1, 2, 2),
+    (Item::Scroll(Scroll::Blinking), 2, 2, 2),
+    (Item::Scroll(Scroll::Teleportation), 2, 2, 2),
+    (Item::Jerkin, 1, 2, 0),
+    (Item::Spear, 1, 1, 0),
+    (Item::Axe, 2, 1, 0),
+    (Item::Rapier, 2, 1, 0),
+    (Item::Dagger, 1, 1, 0),
+    (Item::Bow, 1, 1, 0),
+    (Item::Javelin, 1, 2, 0),
+    (Item::Shield, 2, 1, 1),
+    (Item::Cuirass, 3, 1, 1),
+];
+
+pub(super) fn add_loot<R: Rng>(level: &mut Grid<Tile>, depth: u32, rng: &mut R) {
+    let table = loot_table(depth);
+    let spots: Vec<(Pos, u32)> = grid::inner_positions()
+        .filter(|&pos| is_free(pos, level))
+        .map(|pos| (pos, spot_weight(pos, level)))
+        .collect();
+    place_loot(level, &table, spots, LOOT_PER_LEVEL, rng);
+
+    let exit_pos = grid::positions()
+        .find(|&pos| level[pos].terrain == Terrain::Exit)
+        .expect("Exit not found.");
+    let spots: Vec<(Pos, u32)> = grid::inner_positions()
+        .filter(|&pos| pos.distance(exit_pos) <= EXIT_RADIUS && is_free(pos, level))
+        .map(|pos| (pos, 1))
+        .collect();
+    place_loot(level, &table, spots, EXIT_LOOT, rng);
+}
+
+/// Every item that can be found at a depth, with its weight
+fn loot_table(depth: u32) -> Vec<(Item, u32)> {
+    LOOT.iter()
+        .filter(|&&(_, first_depth, _, _)| first_depth <= depth)
+        .map(|&(item, first_depth, weight, growth)| (item, weight + growth * (depth - first_depth)))
+        .collect()
+}
+
+fn place_loot<R: Rng>(
+    level: &mut Grid<Tile>,
+    table: &[(Item, u32)],
+    mut spots: Vec<(Pos, u32)>,
+    count: u32,
+    rng: &mut R,
+) {
+    for _ in 0..count {
+        if spots.is_empty() {
+            return;
+        }
+        let index = choose_weighted(&spots, rng);
+        let (pos, _) = spots.swap_remove(index);
+        let item = table[choose_weighted(table, rng)].0;
+        level[pos].item = Some(item);
+    }
+}
+
+/// Picks the index of a choice with a chance proportional to its weight.
+fn choose_weighted<T, R: Rng>(choices: &[(T, u32)], rng: &mut R) -> usize {
+    let total: u32 = choices.iter().map(|&(_, weight)| weight).sum();
+    let mut roll = rng.gen_range(0, total);
+    for (index, &(_, weight)) in choices.iter().enumerate() {
+        if roll < weight {
+            return index;
+        }
+        roll -= weight;
+    }
+    unreachable!()
+}
+
+fn is_free(pos: Pos, level: &Grid<Tile>) -> bool {
+    let tile = &level[pos];
+    tile.terrain.passable() && tile.mob_id.is_none() && tile.item.is_none()
+}
+
+fn spot_weight(pos: Pos, level: &Grid<Tile>) -> u32 {
+    if is_dead_end(pos, level) {
+        DEAD_END_WEIGHT
+    } else if is_shore(pos, level) {
+        SHORE_WEIGHT
+    } else {
+        1
+    }
+}
+
+/// Whether a position is at the end of a passage, with its only way out on
+/// one side.
+fn is_dead_end(pos: Pos, level: &Grid<Tile>) -> bool {
+    let open = pos
+        .neighbors()
+        .filter(|&pos| level[pos].terrain.passable())
+        .count();
+    open <= 2 && count_neighbor_groups(pos, level, |tile| tile.terrain.passable()) == 1
+}
+
+fn is_shore(pos: Pos, level: &Grid<Tile>) -> bool {
+    pos.neighbors()
+        .any(|pos| level[pos].terrain == Terrain::Water)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::Architect;
+
+    #[test]
+    fn test_loot_distribution() {
+        let mut potions_and_scrolls = [0; 3];
+        let mut items = [0; 3];
+        let (mut in_dead_ends, mut dead_ends, mut spots) = (0, 0, 0);
+        for seed in 0..40 {
+            let mut architect = Architect::new(seed);
+            for depth in 1..4 {
+                let (level, _) = architect.generate();
+                let exit_pos = grid::positions()
+                    .find(|&pos| level[pos].terrain == Terrain::Exit)
+                    .unwrap();
+                let loot: Vec<Pos> = grid::positions()
+                    .filter(|&pos| level[pos].item.is_some())
+                    .collect();
+                assert_eq!(loot.len() as u32, LOOT_PER_LEVEL + EXIT_LOOT);
+                assert!(loot.iter().any(|pos| pos.distance(exit_pos) <= EXIT_RADIUS));
+                for &pos in &loot {
+                    let item = level[pos].item.unwrap();
+                    let &(_, first_depth, _, _) = LOOT.iter().find(|loot| loot.0 == item).unwrap();
+                    assert!(first_depth <= depth);
+                    items[depth as usize - 1] += 1;
+                    match item {
+                        Item::Potion(_) | Item::Scroll(_) => {
+                            potions_and_scrolls[depth as usize - 1] += 1
+                        }
+                        _ => {}
+                    }
+                    if is_dead_end(pos, &level) {
+                        in_dead_ends += 1;
+                    }
+                }
+                for pos in grid::inner_positions().filter(|&pos| level[pos].terrain.passable()) {
+                    spots += 1;
+                    if is_dead_end(pos, &level) {
+                        dead_ends += 1;
+                    }
+                }
+            }
+        }
+        // Magic gets more common deeper down
+        let share = |depth: usize| f64::from(potions_and_scrolls[depth]) / f64::from(items[depth]);
+        assert!(share(0) < share(2));
+        // Dead ends hold more than their share of loot
+        let dead_end_share = f64::from(dead_ends) / f64::from(spots);
+        let loot_share = f64::from(in_dead_ends) / f64::from(items.iter().sum::<u32>());
+        assert!(loot_share > 2.0 * dead_end_share);
+    }
+}
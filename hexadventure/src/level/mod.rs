@@ -4,6 +4,7 @@ mod basic;
 mod exit;
 mod grass;
 mod lake;
+mod loot;
 mod populate;
 pub mod tile;
 
@@ -13,13 +14,17 @@ use self::tile::{Terrain, Tile};
 use prelude::*;
 use rand::IsaacRng;
 use std::mem::replace;
+use world::item::Appearances;
 use world::mob::Npcs;
 
 /// Responsible for generating levels.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub(super) struct Architect {
     rng: IsaacRng,
     next_level: Grid<Terrain>,
+    /// The depth of the last level generated
+    depth: u32,
+    appearances: Appearances,
 }
 
 impl Architect {
@@ -29,13 +34,31 @@ impl Architect {
             .iter()
             .map(|&t| Terrain::from(t))
             .collect();
-        Architect { rng, next_level }
+        let appearances = Appearances::new(&mut rng);
+        Architect {
+            rng,
+            next_level,
+            depth: 0,
+            appearances,
+        }
+    }
+
+    /// The looks of potions and scrolls, shuffled for this seed
+    pub fn appearances(&self) -> &Appearances {
+        &self.appearances
     }
 
     pub fn generate(&mut self) -> (Grid<Tile>, Npcs) {
         let new_next_level = exit::add_exit(&mut self.next_level, &mut self.rng);
         lake::add_lakes(&mut self.next_level, &mut self.rng);
-        // grass::add_grass(next_level, &mut self.rng);
-        populate(replace(&mut self.next_level, new_next_level), &mut self.rng)
+        grass::add_grass(&mut self.next_level, &mut self.rng);
+        grass::add_bushes(&mut self.next_level, &mut self.rng);
+        grass::add_tall_grass(&mut self.next_level, &mut self.rng);
+        self.depth += 1;
+        populate(
+            replace(&mut self.next_level, new_next_level),
+            self.depth,
+            &mut self.rng,
+        )
     }
 }
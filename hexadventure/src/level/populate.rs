@@ -1,12 +1,30 @@
-//! Populate a level with mobs
+//! Populate a level with mobs and loot
 
 use super::basic::calc_shuffled_positions;
+use super::loot::add_loot;
 use super::tile::{Terrain, Tile};
 use prelude::*;
 use rand::Rng;
+use world::item::Item;
 use world::mob::{Npcs, Species};
 
-pub(super) fn populate<R: Rng>(level: Grid<Terrain>, rng: &mut R) -> (Grid<Tile>, Npcs) {
+/// Equipment a monster may spawn carrying, which it equips when it is idle
+const GEAR: &[Item] = &[
+    Item::Spear,
+    Item::Axe,
+    Item::Rapier,
+    Item::Dagger,
+    Item::Bow,
+    Item::Jerkin,
+    Item::Cuirass,
+    Item::Shield,
+];
+
+pub(super) fn populate<R: Rng>(
+    level: Grid<Terrain>,
+    depth: u32,
+    rng: &mut R,
+) -> (Grid<Tile>, Npcs) {
     fn near_entrance(pos: Pos, level: &Grid<Tile>) -> bool {
         pos.neighbors()
             .any(|pos| level[pos].terrain == Terrain::Entrance)
@@ -16,12 +34,16 @@ pub(super) fn populate<R: Rng>(level: Grid<Terrain>, rng: &mut R) -> (Grid<Tile>
     let mut level = Grid::new(|pos| Tile {
         terrain: level[pos],
         mob_id: None,
+        item: None,
     });
-    let mut npcs = Npcs::new();
+    let mut npcs = Npcs::new(depth);
     let mut npc_count = 0;
     for pos in positions {
         if level[pos].terrain.passable() && !near_entrance(pos, &level) {
-            let mob = Mob::new(pos, Species::Skeleton);
+            let mut mob = Mob::new(pos, Species::Skeleton);
+            if rng.gen() {
+                mob.inventory.push(*rng.choose(GEAR).unwrap());
+            }
             let mob_id = npcs.insert(mob);
             level[pos].mob_id = Some(mob_id);
             npc_count += 1;
@@ -30,16 +52,17 @@ pub(super) fn populate<R: Rng>(level: Grid<Terrain>, rng: &mut R) -> (Grid<Tile>
             }
         }
     }
+    add_loot(&mut level, depth, rng);
     (level, npcs)
 }
 
-/// Place a mob as close as possible to a position in the level
+/// Place a mob on the free tile closest to a position in the level
 pub fn place_mob<R: Rng>(level: &mut Grid<Tile>, center: Pos, mob_id: MobId, rng: &mut R) -> Pos {
     let flip = rng.gen();
     for r in 0.. {
         for pos in center.ring(r) {
             let pos = if flip { center - (pos - center) } else { pos };
-            if level[pos].terrain.passable() {
+            if level[pos].terrain.passable() && level[pos].mob_id.is_none() {
                 level[pos].mob_id = Some(mob_id);
                 return pos;
             }
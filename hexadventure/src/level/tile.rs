@@ -1,12 +1,15 @@
 use prelude::*;
+use world::effect::Effect;
+use world::item::Item;
 
-#[derive(Copy, Clone, Serialize, Deserialize)]
+#[derive(Copy, Clone, Hash, Serialize, Deserialize)]
 pub struct Tile {
     pub terrain: Terrain,
     pub mob_id: Option<MobId>,
+    pub item: Option<Item>,
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash, Serialize, Deserialize)]
 pub enum Terrain {
     Wall,
     Floor,
@@ -18,7 +21,7 @@ pub enum Terrain {
     Water,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum TileView {
     Visible,
     Remembered(Terrain),
@@ -51,6 +54,22 @@ impl Terrain {
         }
     }
 
+    /// An effect and its duration put on mobs that step onto this
+    pub fn effect(&self) -> Option<(Effect, u32)> {
+        match *self {
+            Terrain::TallGrass => Some((Effect::Slow, 2)),
+            _ => None,
+        }
+    }
+
+    /// Whether this leads to another level
+    pub fn is_stairs(&self) -> bool {
+        match *self {
+            Terrain::Exit | Terrain::Entrance => true,
+            _ => false,
+        }
+    }
+
     // pub fn solid(&self) -> bool {
     //     !self.passable() && !self.transparent()
     // }
@@ -1,3 +1,4 @@
+extern crate bincode;
 extern crate rand;
 extern crate serde;
 #[macro_use]
@@ -1,92 +1,958 @@
 use level::place_mob;
-use level::tile::{Terrain, TileView};
+use level::tile::Terrain;
 use prelude::*;
-use rand::{thread_rng, Rng};
-use world::mob::PLAYER_ID;
+use rand::Rng;
+use std::cmp;
+use world::combat::{self, Flank, Shape, Wound};
+use world::effect::{self, Effect};
+use world::event::Event;
+use world::faction;
+use world::item::{self, Item, Slot, INVENTORY_SIZE};
+use world::magic;
+use world::mob::{Species, PLAYER_ID};
+use world::stats::{Dice, Ranged};
+use world::status::{Cause, Death};
 
-pub fn rest(_mob_id: MobId, _world: &mut World) -> Result<(), ()> {
-    Ok(())
+/// Energy spent by each action
+pub const REST_COST: i32 = 50;
+pub const TURN_COST: i32 = 50;
+pub const BRACE_COST: i32 = 100;
+pub const ITEM_COST: i32 = 100;
+pub const EQUIP_COST: i32 = 100;
+pub const HARVEST_COST: i32 = 100;
+pub const CONSUME_COST: i32 = 100;
+pub const WALK_COST: i32 = 100;
+pub const MELEE_COST: i32 = 100;
+pub const LUNGE_COST: i32 = 150;
+pub const SHOOT_COST: i32 = 100;
+pub const SHOVE_COST: i32 = 100;
+pub const RETREAT_COST: i32 = 150;
+
+/// The most berries a bush yields
+const MAX_BERRIES: u32 = 3;
+/// Damage taken by a shoved mob that hits something solid
+const COLLISION_DAMAGE: Dice = Dice { count: 1, sides: 6 };
+/// Turns a shoved mob that can swim is stunned for after landing in water
+const SPLASH_STUN: u32 = 2;
+
+/// What a successful action did
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ActionOutcome {
+    Rested,
+    Turned,
+    Braced,
+    Moved,
+    Retreated,
+    Attacked,
+    /// Stepped forward and attacked
+    Lunged,
+    /// A shot hit nobody
+    Missed,
+    Shoved,
+    PickedUp,
+    Dropped,
+    Equipped,
+    Unequipped,
+    Harvested,
+    Consumed,
+    Descended,
+    Ascended,
+}
+
+/// Why an action could not be taken
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ActionError {
+    /// The terrain in the way can't be walked through
+    Impassable(Terrain),
+    /// The mob won't attack the target, usually because they are on the same side
+    NotHostile,
+    /// There is no mob to attack
+    NoTarget,
+    /// There are no stairs of the right kind next to the mob
+    NoStairs,
+    /// Another mob is in the way, or an item is where one would be put
+    Occupied,
+    /// There is no item here, or none at that place in the inventory
+    NoItem,
+    /// The mob can't carry any more
+    InventoryFull,
+    /// The item can't be equipped
+    NotEquipment,
+    /// The item can't be used up
+    NotConsumable,
+    /// There is no bush to harvest where the mob stands
+    NoBush,
+    /// The target is farther than the mob can shoot
+    OutOfRange,
+    /// The mob has nothing to shoot with
+    NoRangedWeapon,
+    /// The item isn't made for throwing
+    NotThrowable,
+    /// The mob is dead and can't do anything
+    Dead,
+}
+
+pub type ActionResult = Result<ActionOutcome, ActionError>;
+
+/// Waits for a moment, regaining some guard.
+pub fn rest(mob_id: MobId, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let regen = world[mob_id].stats().guard_regen;
+    world[mob_id].restore_guard(regen);
+    world[mob_id].energy -= REST_COST;
+    world.events.push(Event::Rested { mob_id });
+    Ok(ActionOutcome::Rested)
 }
 
-pub fn walk(mob_id: MobId, direction: Direction, world: &mut World) -> Result<(), ()> {
+/// Turns in place to face a direction.
+pub fn turn(mob_id: MobId, direction: Direction, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    world[mob_id].facing = direction;
+    world[mob_id].energy -= TURN_COST;
+    world.events.push(Event::Turned { mob_id, direction });
+    Ok(ActionOutcome::Turned)
+}
+
+/// Spends the turn bracing to block the next hit from the front.
+///
+/// The brace lasts until the mob's next turn.
+pub fn brace(mob_id: MobId, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    world[mob_id].braced = true;
+    world[mob_id].energy -= BRACE_COST;
+    world.events.push(Event::Braced { mob_id });
+    Ok(ActionOutcome::Braced)
+}
+
+pub fn walk(mob_id: MobId, direction: Direction, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
     let target_pos = world[mob_id].pos + direction;
     if world.level[target_pos].mob_id.is_some() {
-        attack_melee(mob_id, direction, world)
+        attack(mob_id, direction, world)
     } else if world.level[target_pos].terrain.passable() {
         if world[mob_id].guard_recovery > 0 && world[mob_id].facing == direction.rotate(3) {
             retreat_unchecked(mob_id, direction, world)
         } else {
-            world.level[target_pos - direction].mob_id = None;
-            world.level[target_pos].mob_id = Some(mob_id);
-            world[mob_id].pos = target_pos;
+            move_unchecked(mob_id, direction, world);
             world[mob_id].facing = direction;
-            Ok(())
+            world[mob_id].energy -= WALK_COST;
+            Ok(ActionOutcome::Moved)
+        }
+    } else if world.level[target_pos].terrain.is_stairs() && mob_id.is_player() {
+        take_stairs_unchecked(target_pos, world)
+    } else {
+        Err(ActionError::Impassable(world.level[target_pos].terrain))
+    }
+}
+
+/// Picks up the item the mob is standing on.
+pub fn pick_up(mob_id: MobId, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let pos = world[mob_id].pos;
+    let item = world.level[pos].item.ok_or(ActionError::NoItem)?;
+    if world[mob_id].inventory.len() >= INVENTORY_SIZE {
+        return Err(ActionError::InventoryFull);
+    }
+    world.level[pos].item = None;
+    world[mob_id].inventory.push(item);
+    world[mob_id].energy -= ITEM_COST;
+    world.events.push(Event::PickedUp { mob_id, item });
+    Ok(ActionOutcome::PickedUp)
+}
+
+/// Puts down the item at an index of the mob's inventory onto its tile.
+pub fn drop_item(mob_id: MobId, index: usize, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    if index >= world[mob_id].inventory.len() {
+        return Err(ActionError::NoItem);
+    }
+    let pos = world[mob_id].pos;
+    if world.level[pos].item.is_some() {
+        return Err(ActionError::Occupied);
+    }
+    let item = world[mob_id].inventory.remove(index);
+    world.level[pos].item = Some(item);
+    world[mob_id].energy -= ITEM_COST;
+    world.events.push(Event::Dropped { mob_id, item });
+    Ok(ActionOutcome::Dropped)
+}
+
+/// Equips the item at an index of the mob's inventory.
+///
+/// Whatever was in its slot goes back into the inventory in its place.
+pub fn equip(mob_id: MobId, index: usize, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let item = *world[mob_id]
+        .inventory
+        .get(index)
+        .ok_or(ActionError::NoItem)?;
+    let slot = item.equip_stats().ok_or(ActionError::NotEquipment)?.slot;
+    let mob = &mut world[mob_id];
+    match mob.equipment.get_mut(slot).replace(item) {
+        Some(old) => mob.inventory[index] = old,
+        None => {
+            mob.inventory.remove(index);
         }
-    } else if world.level[target_pos].terrain == Terrain::Exit {
-        if mob_id.is_player() {
-            world[mob_id].pos = target_pos;
-            descend_unchecked(world);
-            Err(())
+    }
+    mob.energy -= EQUIP_COST;
+    update_max_guard(mob);
+    world.events.push(Event::Equipped { mob_id, item });
+    Ok(ActionOutcome::Equipped)
+}
+
+/// Takes off the item in a slot and puts it in the mob's inventory.
+pub fn unequip(mob_id: MobId, slot: Slot, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let item = world[mob_id]
+        .equipment
+        .get(slot)
+        .ok_or(ActionError::NoItem)?;
+    if world[mob_id].inventory.len() >= INVENTORY_SIZE {
+        return Err(ActionError::InventoryFull);
+    }
+    let mob = &mut world[mob_id];
+    *mob.equipment.get_mut(slot) = None;
+    mob.inventory.push(item);
+    mob.energy -= EQUIP_COST;
+    update_max_guard(mob);
+    world.events.push(Event::Unequipped { mob_id, item });
+    Ok(ActionOutcome::Unequipped)
+}
+
+/// Picks the berries off the bush the mob is standing on, leaving short grass.
+///
+/// Berries that don't fit in the inventory fall to the ground.
+pub fn harvest(mob_id: MobId, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let pos = world[mob_id].pos;
+    if world.level[pos].terrain != Terrain::Brownberry {
+        return Err(ActionError::NoBush);
+    }
+    world.level[pos].terrain = Terrain::ShortGrass;
+    let berries = world.rng.gen_range(1, MAX_BERRIES + 1);
+    for _ in 0..berries {
+        if world[mob_id].inventory.len() < INVENTORY_SIZE {
+            world[mob_id].inventory.push(Item::Brownberry);
         } else {
-            Err(())
+            item::place(pos, Item::Brownberry, world);
         }
-    } else {
-        Err(())
     }
+    world[mob_id].energy -= HARVEST_COST;
+    world.events.push(Event::Harvested { mob_id, berries });
+    Ok(ActionOutcome::Harvested)
 }
 
-fn attack_melee(mob_id: MobId, direction: Direction, world: &mut World) -> Result<(), ()> {
-    let target_pos = world[mob_id].pos + direction;
-    if let Some(target) = world.level[target_pos].mob_id {
-        if mob_id.is_player() || target.is_player() {
-            let damage = thread_rng().gen_range(1, 7) + thread_rng().gen_range(1, 7);
-            let guard = world[target].guard;
-            if damage <= guard {
-                world[target].guard -= damage;
+/// Uses up the item at an index of the mob's inventory.
+pub fn consume(mob_id: MobId, index: usize, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let item = *world[mob_id]
+        .inventory
+        .get(index)
+        .ok_or(ActionError::NoItem)?;
+    if item.magic().is_empty() {
+        return Err(ActionError::NotConsumable);
+    }
+    world[mob_id].inventory.remove(index);
+    world[mob_id].energy -= CONSUME_COST;
+    world.events.push(Event::Consumed { mob_id, item });
+    if mob_id.is_player() {
+        world.identify(item);
+    }
+    for &magic in item.magic() {
+        magic::cast(mob_id, magic, world);
+    }
+    Ok(ActionOutcome::Consumed)
+}
+
+/// Takes the stairs down if the player is standing next to an exit.
+pub fn descend(mob_id: MobId, world: &mut World) -> ActionResult {
+    take_adjacent_stairs(mob_id, Terrain::Exit, world)
+}
+
+/// Takes the stairs up if the player is standing next to an entrance.
+pub fn ascend(mob_id: MobId, world: &mut World) -> ActionResult {
+    take_adjacent_stairs(mob_id, Terrain::Entrance, world)
+}
+
+fn take_adjacent_stairs(mob_id: MobId, stairs: Terrain, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let mob_pos = world[mob_id].pos;
+    let stairs_pos = mob_pos
+        .neighbors()
+        .find(|&pos| world.level[pos].terrain == stairs);
+    match stairs_pos {
+        Some(stairs_pos) if mob_id.is_player() => take_stairs_unchecked(stairs_pos, world),
+        Some(_) => Err(ActionError::Impassable(stairs)),
+        None => Err(ActionError::NoStairs),
+    }
+}
+
+/// Shoots at a position with the mob's ranged weapon, hitting the first mob
+/// in the way.
+pub fn attack_ranged(mob_id: MobId, target_pos: Pos, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let ranged = world[mob_id]
+        .stats()
+        .ranged
+        .ok_or(ActionError::NoRangedWeapon)?;
+    let (outcome, _) = fire(mob_id, target_pos, ranged, world)?;
+    Ok(outcome)
+}
+
+/// Throws the item at an index of the mob's inventory at a position, hitting
+/// the first mob in the way.
+///
+/// The item lands wherever it stopped.
+pub fn throw(mob_id: MobId, index: usize, target_pos: Pos, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let item = *world[mob_id]
+        .inventory
+        .get(index)
+        .ok_or(ActionError::NoItem)?;
+    let ranged = item.throw_stats().ok_or(ActionError::NotThrowable)?;
+    let (outcome, end) = fire(mob_id, target_pos, ranged, world)?;
+    world[mob_id].inventory.remove(index);
+    item::place(end, item, world);
+    Ok(outcome)
+}
+
+/// Sends a shot or a thrown item at a position, and returns where it stopped.
+fn fire(
+    mob_id: MobId,
+    target_pos: Pos,
+    ranged: Ranged,
+    world: &mut World,
+) -> Result<(ActionOutcome, Pos), ActionError> {
+    let mob_pos = world[mob_id].pos;
+    let distance = mob_pos.distance(target_pos);
+    if distance == 0 {
+        return Err(ActionError::NoTarget);
+    }
+    if distance > ranged.range {
+        return Err(ActionError::OutOfRange);
+    }
+    let end = trace(mob_pos, target_pos, world);
+    world.events.push(Event::Shot {
+        mob_id,
+        from: mob_pos,
+        to: end,
+    });
+    world[mob_id].facing = (target_pos - mob_pos).direction();
+    world[mob_id].energy -= SHOOT_COST;
+    match world.level[end].mob_id {
+        Some(target) if faction::can_attack(mob_id, target, world) => {
+            // The shot comes from wherever it was headed when it stopped
+            let direction = (end - mob_pos).direction();
+            hit(
+                mob_id,
+                target,
+                direction,
+                ranged.attack,
+                Cause::Ranged,
+                world,
+            );
+            Ok((ActionOutcome::Attacked, end))
+        }
+        _ => Ok((ActionOutcome::Missed, end)),
+    }
+}
+
+/// Pushes an adjacent mob away.
+///
+/// A mob pushed into a wall or another mob is hurt and stops. A mob pushed
+/// into water drowns or is stunned, depending on its species.
+pub fn shove(mob_id: MobId, direction: Direction, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let target = match world.level[world[mob_id].pos + direction].mob_id {
+        Some(target) => target,
+        None => return Err(ActionError::NoTarget),
+    };
+    if !faction::can_attack(mob_id, target, world) {
+        return Err(ActionError::NotHostile);
+    }
+    world[mob_id].facing = direction;
+    world[mob_id].energy -= SHOVE_COST;
+    combat::engage(mob_id, target, world);
+    faction::provoke(mob_id, target, world);
+    world.events.push(Event::Shoved {
+        attacker: mob_id,
+        target,
+    });
+    let killer = Some(world[mob_id].species);
+    for _ in 0..world[mob_id].stats().push {
+        let from = world[target].pos;
+        let to = from + direction;
+        let tile = world.level[to];
+        if tile.mob_id.is_some() || !(tile.terrain.passable() || tile.terrain == Terrain::Water) {
+            collide(target, killer, world);
+            break;
+        }
+        world.level[from].mob_id = None;
+        world.level[to].mob_id = Some(target);
+        world[target].pos = to;
+        world.events.push(Event::Moved {
+            mob_id: target,
+            from,
+            to,
+        });
+        if tile.terrain == Terrain::Water {
+            if world[target].stats().drowns {
+                let death = Death {
+                    cause: Cause::Drowned,
+                    killer,
+                };
+                target.die(death, world);
             } else {
-                let damage = damage - world[target].guard;
-                world[target].guard = 0;
-                if damage < world[target].health {
-                    world[target].health -= damage;
-                } else {
-                    world[target].health = 0;
-                    target.die(world);
-                }
-            }
-            if world[target].facing == direction.rotate(3) {
-                world[target].guard_recovery = damage / 2;
+                effect::apply(target, Effect::Stun, SPLASH_STUN, killer, world);
             }
-            world[mob_id].facing = direction;
-            Ok(())
-        } else {
-            Err(())
+            break;
+        }
+        enter_terrain(target, world);
+    }
+    Ok(ActionOutcome::Shoved)
+}
+
+/// Finds where a projectile aimed from one position at another stops.
+///
+/// It stops at the first mob in the way, in front of the first opaque
+/// terrain, or at the target.
+pub fn trace(from: Pos, to: Pos, world: &World) -> Pos {
+    let mut end = from;
+    for pos in from.to(to).into_iter().skip(1) {
+        if !grid::contains(pos) {
+            break;
+        }
+        if world.level[pos].mob_id.is_some() {
+            return pos;
+        }
+        if !world.level[pos].terrain.transparent() {
+            break;
+        }
+        end = pos;
+    }
+    end
+}
+
+/// Attacks in a direction, hitting every hex the mob's attack shape covers.
+pub fn attack(mob_id: MobId, direction: Direction, world: &mut World) -> ActionResult {
+    check_alive(mob_id, world)?;
+    let shape = world[mob_id].attack_shape();
+    attack_with(mob_id, shape, direction, world)
+}
+
+fn attack_with(
+    mob_id: MobId,
+    shape: Shape,
+    direction: Direction,
+    world: &mut World,
+) -> ActionResult {
+    let pos = world[mob_id].pos;
+    let ahead = pos + direction;
+    if shape == Shape::Lunge && world.level[ahead].mob_id.is_none() {
+        return lunge(mob_id, direction, world);
+    }
+    // A reach attack can't go through whatever blocks sight in front
+    let targets: Vec<(MobId, Direction)> = shape
+        .targets(pos, direction)
+        .into_iter()
+        .filter(|&(target_pos, _)| {
+            pos.distance(target_pos) == 1 || world.level[ahead].terrain.transparent()
+        })
+        .filter_map(|(target_pos, side)| {
+            world.level[target_pos].mob_id.map(|target| (target, side))
+        })
+        .collect();
+    if targets.is_empty() {
+        return Err(ActionError::NoTarget);
+    }
+    let targets: Vec<(MobId, Direction)> = targets
+        .into_iter()
+        .filter(|&(target, _)| faction::can_attack(mob_id, target, world))
+        .collect();
+    if targets.is_empty() {
+        return Err(ActionError::NotHostile);
+    }
+    let attack = world[mob_id].stats().attack;
+    for (target, side) in targets {
+        hit(mob_id, target, side, attack, Cause::Melee, world);
+    }
+    world[mob_id].facing = direction;
+    world[mob_id].energy -= MELEE_COST;
+    Ok(ActionOutcome::Attacked)
+}
+
+/// Steps into the empty hex in front and strikes the hex beyond it.
+fn lunge(mob_id: MobId, direction: Direction, world: &mut World) -> ActionResult {
+    let ahead = world[mob_id].pos + direction;
+    let terrain = world.level[ahead].terrain;
+    if !terrain.passable() {
+        return Err(ActionError::Impassable(terrain));
+    }
+    let target = match world.level[ahead + direction].mob_id {
+        Some(target) => target,
+        None => return Err(ActionError::NoTarget),
+    };
+    if !faction::can_attack(mob_id, target, world) {
+        return Err(ActionError::NotHostile);
+    }
+    move_unchecked(mob_id, direction, world);
+    let attack = world[mob_id].stats().attack;
+    hit(mob_id, target, direction, attack, Cause::Melee, world);
+    world[mob_id].facing = direction;
+    world[mob_id].energy -= LUNGE_COST;
+    Ok(ActionOutcome::Lunged)
+}
+
+/// Deals damage rolled from `attack` to the target's guard and then its health.
+///
+/// The attack goes in `direction`, as seen from the attacker. See
+/// `combat::Wound` for the rules.
+fn hit(
+    attacker: MobId,
+    target: MobId,
+    direction: Direction,
+    attack: Dice,
+    cause: Cause,
+    world: &mut World,
+) {
+    let on_hit = world[attacker].stats().on_hit;
+    let damage = attack.roll(&mut world.rng);
+    let flank = Flank::of(world[target].facing, direction);
+    combat::engage(attacker, target, world);
+    faction::provoke(attacker, target, world);
+    let invulnerable = target.is_player() && world.invulnerable;
+    let Wound {
+        guard_damage,
+        health_damage,
+        guard_recovery,
+        blocked,
+    } = Wound::new(damage, flank, &world[target], invulnerable);
+    if blocked {
+        world[target].braced = false;
+        world.events.push(Event::Blocked { attacker, target });
+        return;
+    }
+    world[target].guard -= guard_damage;
+    world[target].health -= health_damage;
+    world.events.push(Event::Attacked {
+        attacker,
+        target,
+        flank,
+        guard_damage,
+        health_damage,
+    });
+    if world[target].health == 0 {
+        let killer = Some(world[attacker].species);
+        target.die(Death { cause, killer }, world);
+        return;
+    }
+    if let Some((effect, turns)) = on_hit {
+        if health_damage > 0 {
+            let source = Some(world[attacker].species);
+            effect::apply(target, effect, turns, source, world);
         }
+    }
+    if guard_recovery > 0 {
+        world[target].guard_recovery = guard_recovery;
+        world.events.push(Event::GuardRecovery {
+            mob_id: target,
+            amount: guard_recovery,
+        });
+    }
+}
+
+/// Hurts a shoved mob that hit something solid.
+fn collide(mob_id: MobId, killer: Option<Species>, world: &mut World) {
+    let armor = world[mob_id].stats().armor;
+    let damage = COLLISION_DAMAGE.roll(&mut world.rng).saturating_sub(armor);
+    let damage = if mob_id.is_player() && world.invulnerable {
+        0
     } else {
-        Err(())
+        cmp::min(damage, world[mob_id].health)
+    };
+    world[mob_id].health -= damage;
+    world.events.push(Event::Collided { mob_id, damage });
+    if world[mob_id].health == 0 {
+        let death = Death {
+            cause: Cause::Collision,
+            killer,
+        };
+        mob_id.die(death, world);
     }
 }
 
-fn retreat_unchecked(mob_id: MobId, direction: Direction, world: &mut World) -> Result<(), ()> {
-    let target_pos = world[mob_id].pos + direction;
-    world.level[target_pos - direction].mob_id = None;
-    world.level[target_pos].mob_id = Some(mob_id);
-    world[mob_id].pos = target_pos;
-    world[mob_id].guard += world[mob_id].guard_recovery;
-    Ok(())
-}
-
-fn descend_unchecked(world: &mut World) {
-    let (level, npcs) = world.architect.generate();
-    world.level = level;
-    world.npcs = npcs;
-    let player_pos = place_mob(
-        &mut world.level,
-        world.player.pos,
-        PLAYER_ID,
-        &mut thread_rng(),
-    );
-    world.player.facing = (player_pos - world.player.pos).direction();
+/// Puts the effect of the terrain a mob just stepped onto on it.
+fn enter_terrain(mob_id: MobId, world: &mut World) {
+    let terrain = world.level[world[mob_id].pos].terrain;
+    if let Some((effect, turns)) = terrain.effect() {
+        effect::apply(mob_id, effect, turns, None, world);
+    }
+}
+
+fn check_alive(mob_id: MobId, world: &World) -> Result<(), ActionError> {
+    if world[mob_id].alive {
+        Ok(())
+    } else {
+        Err(ActionError::Dead)
+    }
+}
+
+fn retreat_unchecked(mob_id: MobId, direction: Direction, world: &mut World) -> ActionResult {
+    let recovery = world[mob_id].guard_recovery;
+    world[mob_id].restore_guard(recovery);
+    world[mob_id].energy -= RETREAT_COST;
+    move_unchecked(mob_id, direction, world);
+    Ok(ActionOutcome::Retreated)
+}
+
+/// Matches a mob's maximum guard to its equipment, losing any guard over it.
+fn update_max_guard(mob: &mut Mob) {
+    mob.max_guard = mob.stats().max_guard;
+    mob.guard = cmp::min(mob.guard, mob.max_guard);
+}
+
+/// Moves a mob one hex without checking that the way is clear.
+fn move_unchecked(mob_id: MobId, direction: Direction, world: &mut World) {
+    let from = world[mob_id].pos;
+    let to = from + direction;
+    world.level[from].mob_id = None;
+    world.level[to].mob_id = Some(mob_id);
+    world[mob_id].pos = to;
+    world.events.push(Event::Moved { mob_id, from, to });
+    enter_terrain(mob_id, world);
+}
+
+/// Moves the player through the stairs at a position to the level they lead to.
+///
+/// Exits and entrances are at the same position on the levels they connect,
+/// so the player arrives next to the matching stairs.
+fn take_stairs_unchecked(stairs_pos: Pos, world: &mut World) -> ActionResult {
+    let (depth, outcome, event) = if world.level[stairs_pos].terrain == Terrain::Exit {
+        (world.depth + 1, ActionOutcome::Descended, Event::Descended)
+    } else {
+        (world.depth - 1, ActionOutcome::Ascended, Event::Ascended)
+    };
+    let old_pos = world.player.pos;
+    world.level[old_pos].mob_id = None;
+    world.change_level(depth);
+    let player_pos = place_mob(&mut world.level, stairs_pos, PLAYER_ID, &mut world.rng);
+    world.player.facing = (player_pos - stairs_pos).direction();
     world.player.pos = player_pos;
-    world.fov = Grid::new(|_| TileView::None);
+    world.player.energy -= WALK_COST;
+    world.events.push(event);
     world.update_fov();
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use world::mob::{Mob, Species};
+
+    /// Clears a straight path east of the player, and returns it.
+    fn clear_path(world: &mut World) -> Vec<Pos> {
+        let player_pos = world.player.pos;
+        let path: Vec<Pos> = (1..5).map(|i| player_pos + Direction::East * i).collect();
+        for &pos in &path {
+            world.level[pos].terrain = Terrain::Floor;
+            world.level[pos].mob_id = None;
+        }
+        path
+    }
+
+    fn spawn(pos: Pos, world: &mut World) -> MobId {
+        let mob_id = world.npcs.insert(Mob::new(pos, Species::Skeleton));
+        world.level[pos].mob_id = Some(mob_id);
+        mob_id
+    }
+
+    #[test]
+    fn test_shot_stops_at_first_mob() {
+        let mut world = World::from_seed(3);
+        let player_pos = world.player.pos;
+        let path = clear_path(&mut world);
+        let near = spawn(path[1], &mut world);
+        let far = spawn(path[3], &mut world);
+        assert_eq!(
+            attack_ranged(PLAYER_ID, path[3], &mut world),
+            Err(ActionError::NoRangedWeapon)
+        );
+        world.player.equipment.weapon = Some(Item::Bow);
+
+        let outcome = attack_ranged(PLAYER_ID, path[3], &mut world);
+        assert_eq!(outcome, Ok(ActionOutcome::Attacked));
+        assert!(world[near].guard < world[near].max_guard);
+        assert_eq!(world[far].guard, world[far].max_guard);
+
+        world.level[path[0]].terrain = Terrain::Wall;
+        let outcome = attack_ranged(PLAYER_ID, path[3], &mut world);
+        assert_eq!(outcome, Ok(ActionOutcome::Missed));
+        assert_eq!(trace(player_pos, path[3], &world), player_pos);
+    }
+
+    #[test]
+    fn test_shot_flank_follows_path() {
+        let mut world = World::from_seed(3);
+        let player_pos = world.player.pos;
+        // A target off the six straight lines, whose line starts out in
+        // another direction than it ends up in
+        let first_step = |target_pos: Pos| player_pos.to(target_pos).into_iter().nth(1).unwrap();
+        let target_pos = (1..4)
+            .flat_map(|east| (1..4).map(move |northeast| (east, northeast)))
+            .map(|(east, northeast)| {
+                player_pos + Direction::East * east + Direction::Northeast * northeast
+            })
+            .find(|&pos| {
+                (first_step(pos) - player_pos).direction() != (pos - player_pos).direction()
+            })
+            .unwrap();
+        let line: Vec<Pos> = player_pos.to(target_pos).into_iter().collect();
+        for &pos in &line[1..] {
+            world.level[pos].terrain = Terrain::Floor;
+            world.level[pos].mob_id = None;
+        }
+        let near = spawn(line[1], &mut world);
+        let direction = (line[1] - player_pos).direction();
+        world[near].facing = direction.rotate(3);
+        world.player.equipment.weapon = Some(Item::Bow);
+        world.drain_events();
+        attack_ranged(PLAYER_ID, target_pos, &mut world).unwrap();
+        assert!(world.drain_events().any(|event| match event {
+            Event::Attacked { target, flank, .. } => target == near && flank == Flank::Front,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn test_throw() {
+        let mut world = World::from_seed(3);
+        let path = clear_path(&mut world);
+        let target = spawn(path[2], &mut world);
+        world.player.inventory = vec![Item::Spear, Item::Javelin];
+        assert_eq!(
+            throw(PLAYER_ID, 0, path[2], &mut world),
+            Err(ActionError::NotThrowable)
+        );
+        assert_eq!(
+            throw(PLAYER_ID, 1, path[2], &mut world),
+            Ok(ActionOutcome::Attacked)
+        );
+        assert!(world[target].guard < world[target].max_guard);
+        assert_eq!(world.player.inventory, vec![Item::Spear]);
+        assert!(path[1..4]
+            .iter()
+            .any(|&pos| world.level[pos].item == Some(Item::Javelin)));
+    }
+
+    #[test]
+    fn test_shove() {
+        let mut world = World::from_seed(3);
+        let path = clear_path(&mut world);
+        let skeleton = spawn(path[0], &mut world);
+        world.level[path[2]].terrain = Terrain::Wall;
+        assert_eq!(
+            shove(PLAYER_ID, Direction::East, &mut world),
+            Ok(ActionOutcome::Shoved)
+        );
+        assert_eq!(world[skeleton].pos, path[1]);
+        assert!(world.drain_events().any(|event| match event {
+            Event::Collided { mob_id, .. } => mob_id == skeleton,
+            _ => false,
+        }));
+
+        let mut world = World::from_seed(3);
+        let path = clear_path(&mut world);
+        let skeleton = spawn(path[0], &mut world);
+        world.level[path[1]].terrain = Terrain::Water;
+        shove(PLAYER_ID, Direction::East, &mut world).unwrap();
+        assert!(!skeleton.is_alive(&world));
+    }
+
+    #[test]
+    fn test_attack_shapes() {
+        let mut world = World::from_seed(3);
+        let player_pos = world.player.pos;
+        let path = clear_path(&mut world);
+        let near = spawn(path[0], &mut world);
+        let far = spawn(path[1], &mut world);
+        let outcome = attack_with(PLAYER_ID, Shape::Reach, Direction::East, &mut world);
+        assert_eq!(outcome, Ok(ActionOutcome::Attacked));
+        assert!(world[near].guard < world[near].max_guard);
+        assert!(world[far].guard < world[far].max_guard);
+
+        let mut world = World::from_seed(3);
+        let sides: Vec<MobId> = (-1..2)
+            .map(|n| {
+                let pos = player_pos + Direction::East.rotate(n);
+                world.level[pos].terrain = Terrain::Floor;
+                spawn(pos, &mut world)
+            })
+            .collect();
+        attack_with(PLAYER_ID, Shape::Sweep, Direction::East, &mut world).unwrap();
+        for &mob_id in &sides {
+            assert!(world[mob_id].guard < world[mob_id].max_guard);
+        }
+
+        let mut world = World::from_seed(3);
+        let path = clear_path(&mut world);
+        let target = spawn(path[1], &mut world);
+        let outcome = attack_with(PLAYER_ID, Shape::Lunge, Direction::East, &mut world);
+        assert_eq!(outcome, Ok(ActionOutcome::Lunged));
+        assert_eq!(world.player.pos, path[0]);
+        assert!(world[target].guard < world[target].max_guard);
+    }
+
+    #[test]
+    fn test_brace_blocks_front() {
+        let mut world = World::from_seed(3);
+        let path = clear_path(&mut world);
+        let skeleton = spawn(path[0], &mut world);
+        world[skeleton].facing = Direction::West;
+        turn(PLAYER_ID, Direction::West, &mut world).unwrap();
+        assert_eq!(world.player.facing, Direction::West);
+        brace(PLAYER_ID, &mut world).unwrap();
+
+        // The hit from the rear gets through, unlike the one from the front
+        attack(skeleton, Direction::West, &mut world).unwrap();
+        assert!(world.player.guard < world.player.max_guard);
+        assert!(world.player.braced);
+
+        world.player.facing = Direction::East;
+        let preview = combat::preview(skeleton, PLAYER_ID, Direction::West, &world);
+        assert!(preview.outcomes.iter().all(|&(wound, _)| wound.blocked));
+        let guard = world.player.guard;
+        attack(skeleton, Direction::West, &mut world).unwrap();
+        assert_eq!(world.player.guard, guard);
+        assert!(!world.player.braced);
+    }
+
+    #[test]
+    fn test_pick_up_and_drop() {
+        let mut world = World::from_seed(3);
+        let pos = world.player.pos;
+        world.level[pos].item = None;
+        assert_eq!(pick_up(PLAYER_ID, &mut world), Err(ActionError::NoItem));
+        world.level[pos].item = Some(Item::Spear);
+        assert_eq!(pick_up(PLAYER_ID, &mut world), Ok(ActionOutcome::PickedUp));
+        assert_eq!(world.player.inventory, vec![Item::Spear]);
+        assert_eq!(world.level[pos].item, None);
+
+        world.level[pos].item = Some(Item::Axe);
+        assert_eq!(
+            drop_item(PLAYER_ID, 0, &mut world),
+            Err(ActionError::Occupied)
+        );
+        pick_up(PLAYER_ID, &mut world).unwrap();
+        assert_eq!(
+            drop_item(PLAYER_ID, 0, &mut world),
+            Ok(ActionOutcome::Dropped)
+        );
+        assert_eq!(world.player.inventory, vec![Item::Axe]);
+        assert_eq!(world.level[pos].item, Some(Item::Spear));
+        assert_eq!(
+            drop_item(PLAYER_ID, 5, &mut world),
+            Err(ActionError::NoItem)
+        );
+    }
+
+    #[test]
+    fn test_equipment() {
+        let mut world = World::from_seed(3);
+        let max_guard = world.player.max_guard;
+        world.player.inventory = vec![Item::Shield, Item::Spear, Item::Axe];
+        assert_eq!(equip(PLAYER_ID, 0, &mut world), Ok(ActionOutcome::Equipped));
+        assert_eq!(world.player.max_guard, max_guard + 30);
+        world.player.guard = world.player.max_guard;
+
+        equip(PLAYER_ID, 0, &mut world).unwrap();
+        assert_eq!(world.player.attack_shape(), Shape::Reach);
+        equip(PLAYER_ID, 0, &mut world).unwrap();
+        assert_eq!(world.player.attack_shape(), Shape::Sweep);
+        assert_eq!(world.player.inventory, vec![Item::Spear]);
+
+        assert_eq!(
+            unequip(PLAYER_ID, Slot::Shield, &mut world),
+            Ok(ActionOutcome::Unequipped)
+        );
+        assert_eq!(world.player.guard, max_guard);
+        assert_eq!(
+            unequip(PLAYER_ID, Slot::Armor, &mut world),
+            Err(ActionError::NoItem)
+        );
+        assert_eq!(world.player.inventory, vec![Item::Spear, Item::Shield]);
+    }
+
+    #[test]
+    fn test_harvest_and_eat() {
+        let mut world = World::from_seed(3);
+        let pos = world.player.pos;
+        world.level[pos].terrain = Terrain::Floor;
+        assert_eq!(harvest(PLAYER_ID, &mut world), Err(ActionError::NoBush));
+        world.level[pos].terrain = Terrain::Brownberry;
+        assert_eq!(harvest(PLAYER_ID, &mut world), Ok(ActionOutcome::Harvested));
+        assert_eq!(world.level[pos].terrain, Terrain::ShortGrass);
+        let berries = world.player.inventory.len();
+        assert!(berries >= 1 && berries <= MAX_BERRIES as usize);
+
+        world.player.health = 50;
+        world.player.guard = 0;
+        assert_eq!(
+            consume(PLAYER_ID, 0, &mut world),
+            Ok(ActionOutcome::Consumed)
+        );
+        assert_eq!(world.player.health, 55);
+        assert_eq!(world.player.guard, 20);
+        assert_eq!(world.player.inventory.len(), berries - 1);
+    }
+
+    #[test]
+    fn test_effect_sources() {
+        let mut world = World::from_seed(3);
+        let path = clear_path(&mut world);
+        world.level[path[0]].terrain = Terrain::TallGrass;
+        walk(PLAYER_ID, Direction::East, &mut world).unwrap();
+        assert!(world.player.has_effect(Effect::Slow));
+
+        let skeleton = spawn(path[1], &mut world);
+        world[skeleton].equipment.weapon = Some(Item::Dagger);
+        world[skeleton].facing = Direction::West;
+        world.player.guard = 0;
+        attack(skeleton, Direction::West, &mut world).unwrap();
+        assert!(world.player.has_effect(Effect::Poison));
+        assert!(!world.player.has_effect(Effect::Bleeding));
+    }
+
+    #[test]
+    fn test_retreat_stops_at_max_guard() {
+        let mut world = World::from_seed(3);
+        let path = clear_path(&mut world);
+        world.player.facing = Direction::West;
+        world.player.guard = world.player.max_guard - 3;
+        world.player.guard_recovery = 10;
+        let outcome = walk(PLAYER_ID, Direction::East, &mut world);
+        assert_eq!(outcome, Ok(ActionOutcome::Retreated));
+        assert_eq!(world.player.pos, path[0]);
+        assert_eq!(world.player.guard, world.player.max_guard);
+    }
+
+    #[test]
+    fn test_guard_regeneration() {
+        let mut world = World::from_seed(3);
+        let regen = world.player.stats().guard_regen;
+        world.player.guard = 0;
+        rest(PLAYER_ID, &mut world).unwrap();
+        assert_eq!(world.player.guard, regen);
+
+        world.player.calm = 0;
+        for _ in 0..combat::CALM_TURNS {
+            combat::regenerate(PLAYER_ID, &mut world);
+        }
+        assert_eq!(world.player.guard, regen);
+        combat::regenerate(PLAYER_ID, &mut world);
+        assert_eq!(world.player.guard, 2 * regen);
+
+        world.player.guard = world.player.max_guard;
+        combat::regenerate(PLAYER_ID, &mut world);
+        assert_eq!(world.player.guard, world.player.max_guard);
+    }
 }
@@ -1,29 +1,210 @@
 use grid::DIRECTIONS;
 use prelude::*;
-use rand::{thread_rng, Rng};
-use world::action;
+use rand::Rng;
+use std::cmp::Reverse;
+use world::action::{self, ActionError, ActionResult};
+use world::combat::{self, Shape};
+use world::faction::{self, Faction};
+use world::mob::{self, PLAYER_ID};
 
-pub fn act(mob_id: MobId, world: &mut World) -> Result<(), ()> {
-    let mob_pos = world[mob_id].pos;
-    if world.fov[mob_pos].is_visible() {
-        chase(mob_id, world.player.pos, world)
+/// How close allies with nothing to fight stay to the player
+const FOLLOW_DISTANCE: u32 = 2;
+
+pub fn act(mob_id: MobId, world: &mut World) -> ActionResult {
+    if let Some(target) = find_target(mob_id, world) {
+        let target = world[target].pos;
+        if let Some(direction) = reach_direction(mob_id, target, world) {
+            if let Ok(outcome) = action::attack(mob_id, direction, world) {
+                return Ok(outcome);
+            }
+        }
+        if world[mob_id].pos.distance(target) == 1
+            && world[mob_id].guard * 4 < world[mob_id].max_guard
+            && world.rng.gen_range(0, 2) == 0
+        {
+            return defend(mob_id, target, world);
+        }
+        if can_shoot(mob_id, target, world) && world.rng.gen_range(0, 3) == 0 {
+            action::attack_ranged(mob_id, target, world)
+        } else {
+            chase(mob_id, target, world)
+        }
+    } else if let Some(index) = spare_equipment(mob_id, world) {
+        action::equip(mob_id, index, world)
+    } else if world[mob_id].faction == Faction::Allied
+        && world[mob_id].pos.distance(world.player.pos) > FOLLOW_DISTANCE
+    {
+        let player_pos = world.player.pos;
+        chase(mob_id, player_pos, world)
     } else {
         action::rest(mob_id, world)
     }
 }
 
-pub fn chase(mob_id: MobId, target: Pos, world: &mut World) -> Result<(), ()> {
-    let mut rng = thread_rng();
-    let flip = rng.gen();
+/// Finds an item in a mob's inventory that goes in an empty slot.
+fn spare_equipment(mob_id: MobId, world: &World) -> Option<usize> {
+    let mob = &world[mob_id];
+    mob.inventory
+        .iter()
+        .position(|item| match item.equip_stats() {
+            Some(equip) => mob.equipment.get(equip.slot).is_none(),
+            None => false,
+        })
+}
+
+/// Faces an adjacent target, and braces against it once facing it.
+///
+/// Mobs low on guard do this half the time instead of trading blows.
+fn defend(mob_id: MobId, target: Pos, world: &mut World) -> ActionResult {
+    let direction = (target - world[mob_id].pos).direction();
+    if world[mob_id].facing == direction {
+        action::brace(mob_id, world)
+    } else {
+        action::turn(mob_id, direction, world)
+    }
+}
+
+/// Finds the direction of a target two hexes away in a straight line, if the
+/// mob's attack shape reaches that far.
+fn reach_direction(mob_id: MobId, target: Pos, world: &World) -> Option<Direction> {
+    match world[mob_id].attack_shape() {
+        Shape::Reach | Shape::Lunge => {
+            let pos = world[mob_id].pos;
+            DIRECTIONS
+                .iter()
+                .cloned()
+                .find(|&direction| pos + direction + direction == target)
+        }
+        Shape::Single | Shape::Sweep => None,
+    }
+}
+
+/// Finds the closest mob that a mob can see and wants to attack.
+fn find_target(mob_id: MobId, world: &World) -> Option<MobId> {
+    let mut candidates = vec![PLAYER_ID];
+    mob::for_each(world, |other| candidates.push(other));
+    let mob_pos = world[mob_id].pos;
+    candidates
+        .into_iter()
+        .filter(|&other| faction::is_hostile(mob_id, other, world) && can_see(mob_id, other, world))
+        .min_by_key(|&other| mob_pos.distance(world[other].pos))
+}
+
+/// Whether a mob can see another.
+///
+/// Sight goes both ways, so the player's field of view is used when looking
+/// for the player.
+fn can_see(mob_id: MobId, other: MobId, world: &World) -> bool {
+    let (from, to) = (world[mob_id].pos, world[other].pos);
+    if other.is_player() {
+        world.fov[from].is_visible()
+    } else {
+        from.to(to)
+            .into_iter()
+            .skip(1)
+            .take_while(|&pos| pos != to)
+            .all(|pos| world.level[pos].terrain.transparent())
+    }
+}
+
+pub fn chase(mob_id: MobId, target: Pos, world: &mut World) -> ActionResult {
+    let flip = world.rng.gen();
     let mob_pos = world[mob_id].pos;
-    for &direction in &DIRECTIONS {
-        let direction = if flip { direction.rotate(3) } else { direction };
-        let pos = mob_pos + direction;
-        if pos.distance(target) < mob_pos.distance(target) {
-            if let Ok(x) = action::walk(mob_id, direction, world) {
-                return Ok(x);
+    let mut directions: Vec<Direction> = DIRECTIONS
+        .iter()
+        .map(|&direction| if flip { direction.rotate(3) } else { direction })
+        .filter(|&direction| (mob_pos + direction).distance(target) < mob_pos.distance(target))
+        .collect();
+    if let Some(target_id) = world.level[target].mob_id {
+        // try to come at the target from behind
+        directions.sort_by_key(|&direction| {
+            let pos = mob_pos + direction;
+            let from = if pos == target { mob_pos } else { pos };
+            Reverse(combat::flank(from, target_id, world))
+        });
+    }
+    let mut blocked_by_mob = false;
+    for direction in directions {
+        match action::walk(mob_id, direction, world) {
+            Ok(outcome) => return Ok(outcome),
+            Err(ActionError::NotHostile) => blocked_by_mob = true,
+            Err(_) => {}
+        }
+    }
+    if blocked_by_mob {
+        // step around whoever is in the way
+        for &direction in &DIRECTIONS {
+            let direction = if flip { direction.rotate(3) } else { direction };
+            let pos = mob_pos + direction;
+            if pos.distance(target) == mob_pos.distance(target) {
+                if let Ok(outcome) = action::walk(mob_id, direction, world) {
+                    return Ok(outcome);
+                }
             }
         }
     }
     action::rest(mob_id, world)
 }
+
+/// Whether a mob has something to shoot with and a clear shot at a position
+/// that isn't next to it
+fn can_shoot(mob_id: MobId, target: Pos, world: &World) -> bool {
+    let range = match world[mob_id].stats().ranged {
+        Some(ranged) => ranged.range,
+        None => return false,
+    };
+    let mob_pos = world[mob_id].pos;
+    let distance = mob_pos.distance(target);
+    distance > 1 && distance <= range && action::trace(mob_pos, target, world) == target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::tile::Terrain;
+    use world::event::Event;
+    use world::mob::{Mob, Npcs, Species};
+
+    #[test]
+    fn test_rivals_fight() {
+        let mut world = World::from_seed(3);
+        world.npcs = Npcs::new(world.depth);
+        for pos in grid::positions() {
+            if world.level[pos].mob_id != Some(PLAYER_ID) {
+                world.level[pos].mob_id = None;
+            }
+        }
+        let player_pos = world.player.pos;
+        let pos = grid::inner_positions()
+            .find(|&pos| {
+                pos.distance(player_pos) > 3
+                    && (pos + Direction::East).neighbors().all(grid::contains)
+            })
+            .unwrap();
+        let mut spawn = |pos: Pos, species| {
+            world.level[pos].terrain = Terrain::Floor;
+            let mob_id = world.npcs.insert(Mob::new(pos, species));
+            world.level[pos].mob_id = Some(mob_id);
+            world[mob_id].faction = Faction::Neutral;
+            mob_id
+        };
+        let hero = spawn(pos, Species::Hero);
+        let skeleton = spawn(pos + Direction::East, Species::Skeleton);
+
+        assert_eq!(find_target(hero, &world), Some(skeleton));
+        assert_eq!(find_target(skeleton, &world), Some(hero));
+        world.drain_events();
+        act(hero, &mut world).unwrap();
+        act(skeleton, &mut world).unwrap();
+        let hits: Vec<(MobId, MobId)> = world
+            .drain_events()
+            .filter_map(|event| match event {
+                Event::Attacked {
+                    attacker, target, ..
+                } => Some((attacker, target)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hits, vec![(hero, skeleton), (skeleton, hero)]);
+    }
+}
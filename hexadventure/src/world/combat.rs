@@ -0,0 +1,256 @@
+//! Rules for attacks and for how mobs recover from them.
+
+use prelude::*;
+use std::cmp;
+use world::mob::Mob;
+
+/// Turns without fighting after which a mob starts regenerating guard
+pub const CALM_TURNS: u32 = 5;
+
+/// Which side of its target an attack comes from, relative to where the
+/// target is facing
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Flank {
+    /// The hex the target is facing
+    Front,
+    /// The two hexes beside the front
+    Side,
+    /// The three hexes behind the target
+    Rear,
+}
+
+impl Flank {
+    /// Finds the flank of a target facing `facing` hit by an attack going in `direction`.
+    pub fn of(facing: Direction, direction: Direction) -> Flank {
+        let attacker_side = direction.rotate(3);
+        if attacker_side == facing {
+            Flank::Front
+        } else if attacker_side == facing.rotate(1) || attacker_side == facing.rotate(-1) {
+            Flank::Side
+        } else {
+            Flank::Rear
+        }
+    }
+
+    /// Splits damage into the part that guard can absorb and the part that
+    /// goes straight to health.
+    ///
+    /// Guard is worse at stopping attacks from the side, and worse still at
+    /// stopping attacks from behind.
+    pub fn split(self, damage: u32) -> (u32, u32) {
+        let bypass = match self {
+            Flank::Front => 0,
+            Flank::Side => damage / 4,
+            Flank::Rear => damage / 2,
+        };
+        (damage - bypass, bypass)
+    }
+}
+
+/// Which hexes a melee attack hits
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Shape {
+    /// The hex in front
+    Single,
+    /// The hex in front and the one beyond it, like a spear
+    Reach,
+    /// The hex in front and the two beside it, like an axe
+    Sweep,
+    /// A step forward and a strike at the hex beyond, if the hex in front is empty
+    Lunge,
+}
+
+impl Shape {
+    /// Finds the hexes an attack from `pos` going in `direction` hits, along
+    /// with the direction each one is struck from.
+    ///
+    /// The step of a lunge is handled by the action, so here it is the same as
+    /// a single attack.
+    pub fn targets(self, pos: Pos, direction: Direction) -> Vec<(Pos, Direction)> {
+        match self {
+            Shape::Single | Shape::Lunge => vec![(pos + direction, direction)],
+            Shape::Reach => vec![
+                (pos + direction, direction),
+                (pos + direction + direction, direction),
+            ],
+            Shape::Sweep => (-1..2)
+                .map(|n| {
+                    let side = direction.rotate(n);
+                    (pos + side, side)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What one hit does to its target
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Wound {
+    pub guard_damage: u32,
+    pub health_damage: u32,
+    /// Guard the target may recover by retreating, if it survives
+    pub guard_recovery: u32,
+    /// Whether the target was braced and blocked the hit, which uses up the
+    /// brace
+    pub blocked: bool,
+}
+
+impl Wound {
+    /// Works out what a hit for some damage from a flank does to a target.
+    ///
+    /// Guard absorbs less of an attack from the side or the rear, armor
+    /// softens whatever gets through, and a target hit from the front can
+    /// recover some guard by retreating. A braced target blocks a hit from the
+    /// front completely.
+    pub fn new(damage: u32, flank: Flank, target: &Mob, invulnerable: bool) -> Wound {
+        if flank == Flank::Front && target.braced {
+            return Wound {
+                guard_damage: 0,
+                health_damage: 0,
+                guard_recovery: 0,
+                blocked: true,
+            };
+        }
+        let stats = target.stats();
+        let (guardable, _) = flank.split(damage);
+        let guard_damage = cmp::min(guardable, target.guard);
+        let health_damage = if invulnerable {
+            0
+        } else {
+            let unguarded = (damage - guard_damage).saturating_sub(stats.armor);
+            cmp::min(unguarded, target.health)
+        };
+        let guard_recovery = if flank == Flank::Front && health_damage < target.health {
+            damage * stats.guard_recovery / 100
+        } else {
+            0
+        };
+        Wound {
+            guard_damage,
+            health_damage,
+            guard_recovery,
+            blocked: false,
+        }
+    }
+
+    pub fn kills(&self, target: &Mob) -> bool {
+        self.health_damage >= target.health
+    }
+}
+
+/// Every way an attack could turn out, and what to expect on average
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preview {
+    pub flank: Flank,
+    /// Each possible wound and its probability
+    pub outcomes: Vec<(Wound, f64)>,
+    pub expected_guard_loss: f64,
+    pub expected_health_loss: f64,
+    pub kill_chance: f64,
+    pub expected_guard_recovery: f64,
+}
+
+/// Works out the outcomes of an attack going in a direction without making it.
+pub fn preview(attacker: MobId, target: MobId, direction: Direction, world: &World) -> Preview {
+    let attack = world[attacker].stats().attack;
+    let target_mob = &world[target];
+    let flank = Flank::of(target_mob.facing, direction);
+    let invulnerable = target.is_player() && world.invulnerable;
+    let outcomes: Vec<(Wound, f64)> = attack
+        .distribution()
+        .into_iter()
+        .map(|(damage, chance)| (Wound::new(damage, flank, target_mob, invulnerable), chance))
+        .collect();
+    Preview {
+        flank,
+        expected_guard_loss: expectation(&outcomes, |wound| f64::from(wound.guard_damage)),
+        expected_health_loss: expectation(&outcomes, |wound| f64::from(wound.health_damage)),
+        kill_chance: expectation(
+            &outcomes,
+            |wound| {
+                if wound.kills(target_mob) {
+                    1.0
+                } else {
+                    0.0
+                }
+            },
+        ),
+        expected_guard_recovery: expectation(&outcomes, |wound| f64::from(wound.guard_recovery)),
+        outcomes,
+    }
+}
+
+fn expectation<F>(outcomes: &[(Wound, f64)], f: F) -> f64
+where
+    F: Fn(&Wound) -> f64,
+{
+    outcomes
+        .iter()
+        .map(|&(ref wound, chance)| f(wound) * chance)
+        .sum()
+}
+
+/// Finds the flank of a mob that an attack from a position would hit.
+pub fn flank(from: Pos, target: MobId, world: &World) -> Flank {
+    let target = &world[target];
+    Flank::of(target.facing, (target.pos - from).direction())
+}
+
+/// Marks two mobs as having just fought each other.
+pub(super) fn engage(attacker: MobId, target: MobId, world: &mut World) {
+    world[attacker].calm = 0;
+    world[target].calm = 0;
+}
+
+/// Regenerates the guard of a mob that has been out of combat for a while.
+pub(super) fn regenerate(mob_id: MobId, world: &mut World) {
+    let mob = &mut world[mob_id];
+    if mob.calm >= CALM_TURNS {
+        let regen = mob.stats().guard_regen;
+        mob.restore_guard(regen);
+    }
+    mob.calm = mob.calm.saturating_add(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use world::mob::{self, PLAYER_ID};
+
+    #[test]
+    fn test_flank() {
+        let facing = Direction::East;
+        let flanks: Vec<Flank> = (0..6)
+            .map(|n| Flank::of(facing, facing.rotate(3).rotate(n)))
+            .collect();
+        use self::Flank::*;
+        assert_eq!(flanks, vec![Front, Side, Rear, Rear, Rear, Side]);
+        assert_eq!(Front.split(12), (12, 0));
+        assert_eq!(Side.split(12), (9, 3));
+        assert_eq!(Rear.split(12), (6, 6));
+    }
+
+    #[test]
+    fn test_preview() {
+        let mut world = World::from_seed(3);
+        let mut skeleton = None;
+        mob::for_each(&world, |mob_id| skeleton = skeleton.or(Some(mob_id)));
+        let skeleton = skeleton.unwrap();
+        let direction = world[skeleton].facing.rotate(3);
+        let before = world.state_hash();
+        let preview = preview(PLAYER_ID, skeleton, direction, &world);
+        assert_eq!(world.state_hash(), before);
+        assert_eq!(preview.flank, Flank::Front);
+        assert_eq!(preview.outcomes.len(), 11);
+        assert!((preview.expected_guard_loss - 7.0).abs() < 1e-9);
+        assert_eq!(preview.kill_chance, 0.0);
+        // half of each roll, rounded down
+        assert!((preview.expected_guard_recovery - 117.0 / 36.0).abs() < 1e-9);
+
+        // 2d6 minus one armor kills five health on a roll of 6 or more
+        world[skeleton].guard = 0;
+        world[skeleton].health = 5;
+        let preview = self::preview(PLAYER_ID, skeleton, direction, &world);
+        assert!((preview.kill_chance - 26.0 / 36.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,133 @@
+//! Every level of the dungeon, including those the player has left.
+
+use level::tile::{Tile, TileView};
+use level::Architect;
+use prelude::*;
+use std::mem::replace;
+use world::mob::Npcs;
+
+/// A level along with everything the player left behind on it
+#[derive(Clone, Serialize, Deserialize)]
+pub(super) struct Level {
+    pub tiles: Grid<Tile>,
+    pub fov: Grid<TileView>,
+    pub npcs: Npcs,
+}
+
+/// Owns every level that has been generated so far
+///
+/// Levels are generated in order by the architect, since each level's exit
+/// must line up with the next level's entrance. The level the player is on
+/// is lent out to the world and is missing from here.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Dungeon {
+    architect: Architect,
+    /// Levels indexed by depth minus one
+    levels: Vec<Option<Level>>,
+}
+
+impl Dungeon {
+    pub(super) fn new(architect: Architect) -> Self {
+        Dungeon {
+            architect,
+            levels: Vec::new(),
+        }
+    }
+
+    /// Takes out the level at a depth, generating it and any level above it if necessary.
+    ///
+    /// Panics if the level is already taken out.
+    pub(super) fn take(&mut self, depth: u32) -> Level {
+        while self.levels.len() < depth as usize {
+            let (tiles, npcs) = self.architect.generate();
+            self.levels.push(Some(Level {
+                tiles,
+                fov: Grid::new(|_| TileView::None),
+                npcs,
+            }));
+        }
+        self.levels[depth as usize - 1]
+            .take()
+            .expect("Level is already taken out.")
+    }
+
+    /// Puts back a level that was taken out.
+    fn put(&mut self, depth: u32, level: Level) {
+        self.levels[depth as usize - 1] = Some(level);
+    }
+}
+
+impl World {
+    /// The depth of the level the player is on, starting from 1
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Swaps the current level with the level at another depth.
+    ///
+    /// The player is not placed on the new level.
+    pub(super) fn change_level(&mut self, depth: u32) {
+        let level = self.dungeon.take(depth);
+        let old_level = Level {
+            tiles: replace(&mut self.level, level.tiles),
+            fov: replace(&mut self.fov, level.fov),
+            npcs: replace(&mut self.npcs, level.npcs),
+        };
+        self.dungeon.put(self.depth, old_level);
+        self.depth = depth;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use level::tile::Terrain;
+    use world::action::{self, ActionOutcome};
+    use world::mob::{self, PLAYER_ID};
+
+    fn npc_positions(world: &World) -> Vec<Pos> {
+        let mut positions = Vec::new();
+        mob::for_each(world, |mob_id| positions.push(world[mob_id].pos));
+        positions
+    }
+
+    /// Moves the player next to the stairs of a kind and returns the stairs' position.
+    fn move_next_to(stairs: Terrain, world: &mut World) -> Pos {
+        let stairs_pos = grid::positions()
+            .find(|&pos| world.level[pos].terrain == stairs)
+            .unwrap();
+        let pos = stairs_pos
+            .neighbors()
+            .find(|&pos| world.level[pos].terrain.passable() && world.level[pos].mob_id.is_none())
+            .unwrap();
+        let old_pos = world.player.pos;
+        world.level[old_pos].mob_id = None;
+        world.level[pos].mob_id = Some(PLAYER_ID);
+        world.player.pos = pos;
+        stairs_pos
+    }
+
+    #[test]
+    fn test_return_trip() {
+        let mut world = World::from_seed(11);
+        let exit_pos = move_next_to(Terrain::Exit, &mut world);
+        let npcs = npc_positions(&world);
+        assert_eq!(
+            action::descend(PLAYER_ID, &mut world),
+            Ok(ActionOutcome::Descended)
+        );
+        assert_eq!(world.depth(), 2);
+        assert_eq!(world.level[exit_pos].terrain, Terrain::Entrance);
+
+        move_next_to(Terrain::Entrance, &mut world);
+        assert_eq!(
+            action::ascend(PLAYER_ID, &mut world),
+            Ok(ActionOutcome::Ascended)
+        );
+        assert_eq!(world.depth(), 1);
+        assert_eq!(world.player.pos.distance(exit_pos), 1);
+        assert_eq!(npc_positions(&world), npcs);
+        assert!(world.fov[exit_pos] != TileView::None);
+    }
+}
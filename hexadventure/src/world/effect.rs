@@ -0,0 +1,231 @@
+//! Temporary conditions on mobs, like poison and haste.
+//!
+//! Effects last for a number of the affected mob's turns and tick at the end
+//! of each of them. A turn is measured in time rather than actions, so a
+//! mob that rests twice as often doesn't wear off its effects any faster.
+
+use prelude::*;
+use std::cmp;
+use world::event::Event;
+use world::mob::{Mob, Species};
+use world::status::{Cause, Death};
+
+/// Energy lost by a stunned mob instead of each action
+pub const STUN_COST: i32 = 100;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Effect {
+    /// Loses health every turn
+    Poison,
+    /// Can't act
+    Stun,
+    /// Loses health every turn for every wound
+    Bleeding,
+    /// Acts half as often
+    Slow,
+    /// Acts twice as often
+    Haste,
+}
+
+/// How an effect combines with the same effect already on a mob
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Stacking {
+    /// The longer of the two durations is kept
+    Refresh,
+    /// The durations are added together
+    Extend,
+    /// The effect gets stronger, and the longer duration is kept
+    Intensify,
+}
+
+/// An effect on a mob
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ActiveEffect {
+    pub effect: Effect,
+    /// The number of turns left
+    pub turns: u32,
+    /// How many times the effect was applied, for effects that intensify
+    pub stacks: u32,
+    /// The species of whoever last put the effect on the mob, if anyone did
+    pub source: Option<Species>,
+}
+
+impl Effect {
+    pub fn stacking(self) -> Stacking {
+        match self {
+            Effect::Poison => Stacking::Extend,
+            Effect::Bleeding => Stacking::Intensify,
+            Effect::Stun | Effect::Slow | Effect::Haste => Stacking::Refresh,
+        }
+    }
+
+    /// The effect that this one removes when applied
+    fn opposite(self) -> Option<Effect> {
+        match self {
+            Effect::Slow => Some(Effect::Haste),
+            Effect::Haste => Some(Effect::Slow),
+            _ => None,
+        }
+    }
+
+    /// Health lost at the end of each turn for each stack
+    fn damage(self) -> u32 {
+        match self {
+            Effect::Poison | Effect::Bleeding => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl Mob {
+    pub fn has_effect(&self, effect: Effect) -> bool {
+        self.effects.iter().any(|active| active.effect == effect)
+    }
+
+    /// The energy gained each time step, after slow and haste
+    ///
+    /// This is never zero, so that even a slowed mob gets to act eventually.
+    pub fn current_speed(&self) -> u32 {
+        if self.has_effect(Effect::Haste) {
+            self.speed * 2
+        } else if self.has_effect(Effect::Slow) {
+            cmp::max(self.speed / 2, 1)
+        } else {
+            self.speed
+        }
+    }
+}
+
+/// Puts an effect on a mob for a number of its turns.
+///
+/// `source` is the species of whoever caused it, who is blamed if the effect
+/// kills the mob.
+pub fn apply(
+    mob_id: MobId,
+    effect: Effect,
+    turns: u32,
+    source: Option<Species>,
+    world: &mut World,
+) {
+    {
+        let effects = &mut world[mob_id].effects;
+        if let Some(opposite) = effect.opposite() {
+            effects.retain(|active| active.effect != opposite);
+        }
+        match effects.iter_mut().find(|active| active.effect == effect) {
+            Some(active) => {
+                match effect.stacking() {
+                    Stacking::Refresh => active.turns = cmp::max(active.turns, turns),
+                    Stacking::Extend => active.turns += turns,
+                    Stacking::Intensify => {
+                        active.turns = cmp::max(active.turns, turns);
+                        active.stacks += 1;
+                    }
+                }
+                active.source = source.or(active.source);
+            }
+            None => effects.push(ActiveEffect {
+                effect,
+                turns,
+                stacks: 1,
+                source,
+            }),
+        }
+    }
+    world.events.push(Event::EffectStarted { mob_id, effect });
+}
+
+/// Deals damage from the effects on a mob and counts down their durations.
+pub(super) fn end_turn(mob_id: MobId, world: &mut World) {
+    let effects = world[mob_id].effects.clone();
+    for active in &effects {
+        let damage = active.effect.damage() * active.stacks;
+        if damage == 0 {
+            continue;
+        }
+        let damage = if mob_id.is_player() && world.invulnerable {
+            0
+        } else {
+            cmp::min(damage, world[mob_id].health)
+        };
+        world[mob_id].health -= damage;
+        world.events.push(Event::EffectDamage {
+            mob_id,
+            effect: active.effect,
+            damage,
+        });
+        if world[mob_id].health == 0 {
+            let death = Death {
+                cause: Cause::Effect(active.effect),
+                killer: active.source,
+            };
+            mob_id.die(death, world);
+            return;
+        }
+    }
+    for active in &mut world[mob_id].effects {
+        active.turns = active.turns.saturating_sub(1);
+    }
+    let expired: Vec<Effect> = world[mob_id]
+        .effects
+        .iter()
+        .filter(|active| active.turns == 0)
+        .map(|active| active.effect)
+        .collect();
+    world[mob_id].effects.retain(|active| active.turns > 0);
+    for effect in expired {
+        world.events.push(Event::EffectEnded { mob_id, effect });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use world::mob::PLAYER_ID;
+    use world::status::Status;
+
+    #[test]
+    fn test_stacking() {
+        let mut world = World::from_seed(3);
+        apply(PLAYER_ID, Effect::Poison, 2, None, &mut world);
+        apply(PLAYER_ID, Effect::Poison, 3, None, &mut world);
+        apply(PLAYER_ID, Effect::Bleeding, 2, None, &mut world);
+        apply(PLAYER_ID, Effect::Bleeding, 1, None, &mut world);
+        apply(PLAYER_ID, Effect::Slow, 4, None, &mut world);
+        apply(PLAYER_ID, Effect::Haste, 1, None, &mut world);
+        assert!(!world.player.has_effect(Effect::Slow));
+        assert_eq!(world.player.current_speed(), world.player.speed * 2);
+
+        let health = world.player.health;
+        end_turn(PLAYER_ID, &mut world);
+        assert_eq!(world.player.health, health - 3);
+        assert!(!world.player.has_effect(Effect::Haste));
+        end_turn(PLAYER_ID, &mut world);
+        assert!(!world.player.has_effect(Effect::Bleeding));
+        for _ in 0..3 {
+            end_turn(PLAYER_ID, &mut world);
+        }
+        assert!(world.player.effects.is_empty());
+        assert_eq!(world.player.health, health - 3 - 3 - 3);
+    }
+
+    #[test]
+    fn test_effect_death_credits_source() {
+        let mut world = World::from_seed(3);
+        apply(
+            PLAYER_ID,
+            Effect::Bleeding,
+            5,
+            Some(Species::Skeleton),
+            &mut world,
+        );
+        apply(PLAYER_ID, Effect::Bleeding, 5, None, &mut world);
+        world.player.health = 1;
+        end_turn(PLAYER_ID, &mut world);
+        let death = Death {
+            cause: Cause::Effect(Effect::Bleeding),
+            killer: Some(Species::Skeleton),
+        };
+        assert_eq!(world.status(), Status::Dead(death));
+    }
+}
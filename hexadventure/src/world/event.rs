@@ -0,0 +1,138 @@
+//! Things that happened in the world.
+//!
+//! Frontends drain these after each tick to drive message logs, animations
+//! and sounds without diffing the whole world.
+
+use prelude::*;
+use world::combat::Flank;
+use world::effect::Effect;
+use world::item::Item;
+use world::mob::Species;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    /// A mob stepped from one position to another
+    Moved { mob_id: MobId, from: Pos, to: Pos },
+    /// A mob hit another from one of its flanks, with the damage split between
+    /// guard and health
+    Attacked {
+        attacker: MobId,
+        target: MobId,
+        flank: Flank,
+        guard_damage: u32,
+        health_damage: u32,
+    },
+    /// A braced mob blocked a hit from the front
+    Blocked { attacker: MobId, target: MobId },
+    /// A mob waited for a moment
+    Rested { mob_id: MobId },
+    /// A mob turned in place to face a direction
+    Turned { mob_id: MobId, direction: Direction },
+    /// A mob braced itself to block the next hit from the front
+    Braced { mob_id: MobId },
+    /// A mob picked up the item it was standing on
+    PickedUp { mob_id: MobId, item: Item },
+    /// A mob put down an item it was carrying
+    Dropped { mob_id: MobId, item: Item },
+    /// A mob equipped an item from its inventory
+    Equipped { mob_id: MobId, item: Item },
+    /// A mob put an equipped item back in its inventory
+    Unequipped { mob_id: MobId, item: Item },
+    /// A mob picked the berries off the bush it was standing on
+    Harvested { mob_id: MobId, berries: u32 },
+    /// A mob used up an item from its inventory
+    Consumed { mob_id: MobId, item: Item },
+    /// A mob regained health
+    Healed { mob_id: MobId, amount: u32 },
+    /// A mob regained guard other than by resting or retreating
+    GuardRestored { mob_id: MobId, amount: u32 },
+    /// A mob was moved by magic
+    Teleported { mob_id: MobId, from: Pos, to: Pos },
+    /// The player learned what a kind of item does by using one
+    Identified(Item),
+    /// A mob shot or threw something that flew from one position to another
+    Shot { mob_id: MobId, from: Pos, to: Pos },
+    /// A mob shoved another
+    Shoved { attacker: MobId, target: MobId },
+    /// A shoved mob was hurt by hitting something solid
+    Collided { mob_id: MobId, damage: u32 },
+    /// A mob may recover this much guard by retreating on its next turn
+    GuardRecovery { mob_id: MobId, amount: u32 },
+    /// A mob came under an effect, or had it renewed
+    EffectStarted { mob_id: MobId, effect: Effect },
+    /// An effect on a mob ran out
+    EffectEnded { mob_id: MobId, effect: Effect },
+    /// A mob lost health to an effect
+    EffectDamage {
+        mob_id: MobId,
+        effect: Effect,
+        damage: u32,
+    },
+    /// A mob was killed
+    Died { mob_id: MobId, species: Species },
+    /// The player went down the stairs
+    Descended,
+    /// The player went up the stairs
+    Ascended,
+    /// The player saw a position for the first time on this level
+    Revealed(Pos),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::tile::Terrain;
+    use world::action;
+    use world::mob::{Mob, PLAYER_ID};
+
+    #[test]
+    fn test_event_stream() {
+        let mut world = World::from_seed(3);
+        let from = world.player.pos;
+        let to = from + Direction::East;
+        world.level[to].terrain = Terrain::Floor;
+        world.level[to].mob_id = None;
+        world.drain_events();
+        action::walk(PLAYER_ID, Direction::East, &mut world).unwrap();
+        let moved = Event::Moved {
+            mob_id: PLAYER_ID,
+            from,
+            to,
+        };
+        assert!(world.drain_events().any(|event| event == moved));
+
+        let pos = to + Direction::East;
+        world.level[pos].terrain = Terrain::Floor;
+        let skeleton = world.npcs.insert(Mob::new(pos, Species::Skeleton));
+        world.level[pos].mob_id = Some(skeleton);
+        world[skeleton].facing = Direction::West;
+        world[skeleton].guard = 1;
+        let health = world[skeleton].health;
+        action::attack(PLAYER_ID, Direction::East, &mut world).unwrap();
+        let events: Vec<Event> = world.drain_events().collect();
+        match events[0] {
+            Event::Attacked {
+                attacker,
+                target,
+                flank,
+                guard_damage,
+                health_damage,
+            } => {
+                assert_eq!((attacker, target), (PLAYER_ID, skeleton));
+                assert_eq!(flank, Flank::Front);
+                assert_eq!(guard_damage, 1);
+                assert_eq!(world[skeleton].health, health - health_damage);
+            }
+            ref event => panic!("Expected an attack, got {:?}", event),
+        }
+
+        world[skeleton].guard = 0;
+        world[skeleton].health = 1;
+        action::attack(PLAYER_ID, Direction::East, &mut world).unwrap();
+        let died = Event::Died {
+            mob_id: skeleton,
+            species: Species::Skeleton,
+        };
+        assert!(world.drain_events().any(|event| event == died));
+    }
+}
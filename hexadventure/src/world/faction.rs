@@ -0,0 +1,94 @@
+//! Who fights whom.
+
+use prelude::*;
+use world::mob::Species;
+
+/// Which side a mob is on
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Faction {
+    /// On the player's side, like the player and their pets
+    Allied,
+    /// Leaves everyone alone until attacked
+    Neutral,
+    /// Against the player and their allies
+    Hostile,
+}
+
+/// Pairs of species that fight each other whatever their factions
+const RIVALRIES: &[(Species, Species)] = &[(Species::Hero, Species::Skeleton)];
+
+/// Whether a mob is willing to attack another of its own accord.
+///
+/// Npcs of rival species fight unless they are on the same side. The player's
+/// quarrels are settled by faction alone, so a neutral skeleton still leaves
+/// them be until attacked.
+pub fn is_hostile(mob_id: MobId, other_id: MobId, world: &World) -> bool {
+    if mob_id == other_id {
+        return false;
+    }
+    let (mob, other) = (&world[mob_id], &world[other_id]);
+    let rivals = !mob_id.is_player()
+        && !other_id.is_player()
+        && (mob.faction != other.faction || mob.faction == Faction::Neutral)
+        && RIVALRIES.iter().any(|&(a, b)| {
+            (a, b) == (mob.species, other.species) || (b, a) == (mob.species, other.species)
+        });
+    let opposed = |a, b| mob.faction == a && other.faction == b;
+    rivals
+        || opposed(Faction::Allied, Faction::Hostile)
+        || opposed(Faction::Hostile, Faction::Allied)
+}
+
+/// Whether a mob may attack another.
+///
+/// The player may attack anything but their allies.
+pub fn can_attack(attacker: MobId, target: MobId, world: &World) -> bool {
+    if attacker.is_player() {
+        world[target].faction != Faction::Allied
+    } else {
+        is_hostile(attacker, target, world)
+    }
+}
+
+/// Turns a neutral mob against the player's side once one of them attacks it.
+pub(super) fn provoke(attacker: MobId, target: MobId, world: &mut World) {
+    if world[attacker].faction == Faction::Allied && world[target].faction == Faction::Neutral {
+        world[target].faction = Faction::Hostile;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::tile::Terrain;
+    use world::action::{self, ActionError, ActionOutcome};
+    use world::mob::{Mob, PLAYER_ID};
+
+    #[test]
+    fn test_infighting() {
+        let mut world = World::from_seed(3);
+        let mut pos = world.player.pos;
+        let mut skeletons = Vec::new();
+        for _ in 0..3 {
+            pos += Direction::East;
+            world.level[pos].terrain = Terrain::Floor;
+            let mob_id = world.npcs.insert(Mob::new(pos, Species::Skeleton));
+            world.level[pos].mob_id = Some(mob_id);
+            skeletons.push(mob_id);
+        }
+        let (a, b) = (skeletons[0], skeletons[1]);
+
+        let outcome = action::walk(b, Direction::East, &mut world);
+        assert_eq!(outcome, Err(ActionError::NotHostile));
+
+        world[a].faction = Faction::Allied;
+        assert!(!can_attack(PLAYER_ID, a, &world));
+        let outcome = action::walk(a, Direction::East, &mut world);
+        assert_eq!(outcome, Ok(ActionOutcome::Attacked));
+
+        world[a].faction = Faction::Neutral;
+        assert!(!is_hostile(a, PLAYER_ID, &world));
+        action::walk(PLAYER_ID, Direction::East, &mut world).unwrap();
+        assert!(is_hostile(a, PLAYER_ID, &world));
+    }
+}
@@ -0,0 +1,116 @@
+//! Snapshots of the world taken before each player command, so that turns can
+//! be taken back.
+//!
+//! Undo is off by default. Every snapshot is a full copy of the world, so only
+//! a bounded number of the most recent ones are kept.
+
+use prelude::*;
+use std::collections::VecDeque;
+use std::mem;
+
+/// The most recent snapshots, oldest first
+#[derive(Default)]
+pub struct History {
+    /// How many snapshots to keep, or zero if undo is off
+    capacity: usize,
+    snapshots: VecDeque<World>,
+}
+
+/// A copy of a history keeps its capacity but none of its snapshots, so that
+/// neither cloning a world nor taking a snapshot of it copies every snapshot
+/// before it.
+impl Clone for History {
+    fn clone(&self) -> Self {
+        History {
+            capacity: self.capacity,
+            snapshots: VecDeque::new(),
+        }
+    }
+}
+
+impl World {
+    /// Keeps snapshots of up to `capacity` turns. Zero turns undo off.
+    pub fn set_undo_capacity(&mut self, capacity: usize) {
+        self.history.capacity = capacity;
+        while self.history.snapshots.len() > capacity {
+            self.history.snapshots.pop_front();
+        }
+    }
+
+    /// How many turns can be undone right now
+    pub fn undo_depth(&self) -> usize {
+        self.history.snapshots.len()
+    }
+
+    /// Rolls the world back to how it was before the last `turns` turns.
+    ///
+    /// Does nothing and returns false if fewer turns were kept.
+    pub fn undo(&mut self, turns: usize) -> bool {
+        let len = self.history.snapshots.len();
+        if turns == 0 || turns > len {
+            return false;
+        }
+        let mut history = mem::take(&mut self.history);
+        history.snapshots.truncate(len - turns + 1);
+        *self = history.snapshots.pop_back().unwrap();
+        self.history = history;
+        true
+    }
+
+    /// Copies the world without its history, if undo is on.
+    pub(super) fn snapshot(&self) -> Option<World> {
+        if self.history.capacity == 0 {
+            return None;
+        }
+        let mut snapshot = self.clone();
+        snapshot.events.clear();
+        Some(snapshot)
+    }
+
+    pub(super) fn push_snapshot(&mut self, snapshot: World) {
+        if self.history.snapshots.len() == self.history.capacity {
+            self.history.snapshots.pop_front();
+        }
+        self.history.snapshots.push_back(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use world::replay::Command;
+
+    #[test]
+    fn test_undo() {
+        let mut world = World::from_seed(3);
+        world.set_undo_capacity(2);
+        let start = world.state_hash();
+        world.command(Command::Rest).unwrap();
+        let after_one = world.state_hash();
+        world.command(Command::Rest).unwrap();
+        world.command(Command::Rest).unwrap();
+        assert_eq!(world.undo_depth(), 2);
+        assert!(!world.undo(3));
+        assert!(world.undo(2));
+        assert_eq!(world.state_hash(), after_one);
+        assert_eq!(world.replay().commands.len(), 1);
+        assert!(!world.undo(1));
+        assert_ne!(world.state_hash(), start);
+    }
+
+    #[test]
+    fn test_snapshots_hold_no_history() {
+        let mut world = World::from_seed(3);
+        world.set_undo_capacity(3);
+        for _ in 0..10 {
+            world.command(Command::Rest).unwrap();
+        }
+        assert_eq!(world.undo_depth(), 3);
+        assert!(world
+            .history
+            .snapshots
+            .iter()
+            .all(|snapshot| snapshot.undo_depth() == 0));
+        assert_eq!(world.clone().undo_depth(), 0);
+    }
+}
@@ -0,0 +1,267 @@
+//! Things that can lie on the floor or be carried.
+
+use prelude::*;
+use rand::Rng;
+use std::mem;
+use world::effect::Effect;
+use world::event::Event;
+use world::magic::Magic;
+
+/// The number of items a mob can carry
+pub const INVENTORY_SIZE: usize = 10;
+
+/// Something that can be picked up
+///
+/// Each tile holds at most one item.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Item {
+    Spear,
+    Axe,
+    Rapier,
+    Dagger,
+    Bow,
+    Javelin,
+    Jerkin,
+    Cuirass,
+    Shield,
+    Brownberry,
+    Potion(Potion),
+    Scroll(Scroll),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Potion {
+    Healing,
+    Fortitude,
+    Swiftness,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Scroll {
+    Teleportation,
+    Mapping,
+    Blinking,
+}
+
+pub const POTIONS: [Potion; 3] = [Potion::Healing, Potion::Fortitude, Potion::Swiftness];
+pub const SCROLLS: [Scroll; 3] = [Scroll::Teleportation, Scroll::Mapping, Scroll::Blinking];
+
+impl Item {
+    /// Every kind of item
+    pub fn kinds() -> Vec<Item> {
+        let mut kinds = vec![
+            Item::Spear,
+            Item::Axe,
+            Item::Rapier,
+            Item::Dagger,
+            Item::Bow,
+            Item::Javelin,
+            Item::Jerkin,
+            Item::Cuirass,
+            Item::Shield,
+            Item::Brownberry,
+        ];
+        kinds.extend(POTIONS.iter().map(|&potion| Item::Potion(potion)));
+        kinds.extend(SCROLLS.iter().map(|&scroll| Item::Scroll(scroll)));
+        kinds
+    }
+
+    /// What using up the item casts, or nothing if it can't be used up
+    pub fn magic(self) -> &'static [Magic] {
+        match self {
+            Item::Brownberry => &[Magic::Heal(5), Magic::RestoreGuard(20)],
+            Item::Potion(Potion::Healing) => &[Magic::Heal(40)],
+            Item::Potion(Potion::Fortitude) => &[Magic::RestoreGuard(100)],
+            Item::Potion(Potion::Swiftness) => &[Magic::Apply(Effect::Haste, 10)],
+            Item::Scroll(Scroll::Teleportation) => &[Magic::Teleport],
+            Item::Scroll(Scroll::Mapping) => &[Magic::MagicMap],
+            Item::Scroll(Scroll::Blinking) => &[Magic::Blink(4)],
+            _ => &[],
+        }
+    }
+}
+
+/// What an unidentified potion looks like
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Color {
+    Red,
+    Blue,
+    Green,
+    Gray,
+}
+
+/// What is written on an unidentified scroll
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Label {
+    Xam,
+    Zot,
+    Pleh,
+    Ruk,
+    Vox,
+}
+
+/// The looks of each kind of potion and scroll in one game
+///
+/// These are shuffled for every seed, so that what a potion does has to be
+/// learned by drinking it.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Appearances {
+    /// The color of each potion in `POTIONS`
+    colors: Vec<Color>,
+    /// The label of each scroll in `SCROLLS`
+    labels: Vec<Label>,
+}
+
+impl Appearances {
+    pub fn new<R: Rng>(rng: &mut R) -> Self {
+        let mut colors = vec![Color::Red, Color::Blue, Color::Green, Color::Gray];
+        let mut labels = vec![Label::Xam, Label::Zot, Label::Pleh, Label::Ruk, Label::Vox];
+        rng.shuffle(&mut colors);
+        rng.shuffle(&mut labels);
+        Appearances { colors, labels }
+    }
+
+    pub fn color(&self, potion: Potion) -> Color {
+        let index = POTIONS.iter().position(|&p| p == potion).unwrap();
+        self.colors[index]
+    }
+
+    pub fn label(&self, scroll: Scroll) -> Label {
+        let index = SCROLLS.iter().position(|&s| s == scroll).unwrap();
+        self.labels[index]
+    }
+}
+
+/// Where a piece of equipment is worn or wielded
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Slot {
+    Weapon,
+    Armor,
+    Shield,
+}
+
+/// The items a mob has equipped, at most one in each slot
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Equipment {
+    pub weapon: Option<Item>,
+    pub armor: Option<Item>,
+    pub shield: Option<Item>,
+}
+
+impl Equipment {
+    pub fn get(&self, slot: Slot) -> Option<Item> {
+        match slot {
+            Slot::Weapon => self.weapon,
+            Slot::Armor => self.armor,
+            Slot::Shield => self.shield,
+        }
+    }
+
+    pub fn get_mut(&mut self, slot: Slot) -> &mut Option<Item> {
+        match slot {
+            Slot::Weapon => &mut self.weapon,
+            Slot::Armor => &mut self.armor,
+            Slot::Shield => &mut self.shield,
+        }
+    }
+
+    /// Every equipped item
+    pub fn items(&self) -> impl Iterator<Item = Item> {
+        self.weapon.into_iter().chain(self.armor).chain(self.shield)
+    }
+}
+
+impl World {
+    /// The looks of potions and scrolls in this game
+    pub fn appearances(&self) -> &Appearances {
+        &self.appearances
+    }
+
+    /// Whether the player knows what an item does.
+    ///
+    /// Potions and scrolls have to be used once to be identified.
+    pub fn is_identified(&self, item: Item) -> bool {
+        match item {
+            Item::Potion(_) | Item::Scroll(_) => self.identified.contains(&item),
+            _ => true,
+        }
+    }
+
+    /// Teaches the player what a kind of item does.
+    pub(super) fn identify(&mut self, item: Item) {
+        if !self.is_identified(item) {
+            self.identified.push(item);
+            self.events.push(Event::Identified(item));
+        }
+    }
+}
+
+/// Puts an item on a free, passable tile at or next to a position.
+///
+/// Returns where the item landed, or `None` if there was no room and the
+/// item was lost.
+pub fn place(pos: Pos, item: Item, world: &mut World) -> Option<Pos> {
+    let free = |pos: Pos| {
+        let tile = &world.level[pos];
+        tile.terrain.passable() && tile.item.is_none()
+    };
+    let landing = Some(pos)
+        .into_iter()
+        .chain(pos.neighbors())
+        .find(|&pos| free(pos))?;
+    world.level[landing].item = Some(item);
+    Some(landing)
+}
+
+/// Drops everything a mob was carrying or had equipped around it.
+pub(super) fn scatter(mob_id: MobId, world: &mut World) {
+    let pos = world[mob_id].pos;
+    let mut items = mem::take(&mut world[mob_id].inventory);
+    items.extend(mem::take(&mut world[mob_id].equipment).items());
+    for item in items {
+        place(pos, item, world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::tile::Terrain;
+    use world::action;
+    use world::mob::PLAYER_ID;
+
+    #[test]
+    fn test_place_spills_over() {
+        let mut world = World::from_seed(3);
+        let pos = world.player.pos;
+        for neighbor in pos.neighbors() {
+            world.level[neighbor].terrain = Terrain::Floor;
+            world.level[neighbor].item = None;
+        }
+        world.level[pos].item = None;
+        assert_eq!(place(pos, Item::Spear, &mut world), Some(pos));
+        let landing = place(pos, Item::Axe, &mut world).unwrap();
+        assert_eq!(landing.distance(pos), 1);
+        assert_eq!(world.level[landing].item, Some(Item::Axe));
+    }
+
+    #[test]
+    fn test_identify_by_using() {
+        let mut world = World::from_seed(3);
+        let potion = Item::Potion(Potion::Healing);
+        assert!(!world.is_identified(potion));
+        assert!(world.is_identified(Item::Spear));
+        world.player.inventory.push(potion);
+        world.player.health = 10;
+        action::consume(PLAYER_ID, 0, &mut world).unwrap();
+        assert!(world.is_identified(potion));
+        assert_eq!(world.player.health, 50);
+    }
+
+    #[test]
+    fn test_appearances_depend_on_seed() {
+        let appearances = |seed| World::from_seed(seed).appearances().clone();
+        assert_eq!(appearances(1), appearances(1));
+        assert!((2..10).any(|seed| appearances(seed) != appearances(1)));
+    }
+}
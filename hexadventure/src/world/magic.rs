@@ -0,0 +1,145 @@
+//! Magic that changes the world, from potions, scrolls and food.
+//!
+//! Nothing here depends on where the magic came from, so monster abilities
+//! can cast the same things items do.
+
+use level::tile::TileView;
+use prelude::*;
+use rand::Rng;
+use std::cmp;
+use world::effect::{self, Effect};
+use world::event::Event;
+
+/// Something that happens to the mob casting it, or to the world around it
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Magic {
+    Heal(u32),
+    RestoreGuard(u32),
+    /// Moves to a random free, passable position on the level
+    Teleport,
+    /// Lets the player remember the whole level
+    MagicMap,
+    /// Moves up to this many hexes straight ahead, stopping at anything in the way
+    Blink(u32),
+    /// Puts an effect on the caster for a number of its turns
+    Apply(Effect, u32),
+}
+
+pub fn cast(mob_id: MobId, magic: Magic, world: &mut World) {
+    match magic {
+        Magic::Heal(amount) => {
+            let mob = &mut world[mob_id];
+            let amount = cmp::min(amount, mob.max_health.saturating_sub(mob.health));
+            mob.health += amount;
+            world.events.push(Event::Healed { mob_id, amount });
+        }
+        Magic::RestoreGuard(amount) => {
+            let amount = world[mob_id].restore_guard(amount);
+            world.events.push(Event::GuardRestored { mob_id, amount });
+        }
+        Magic::Teleport => {
+            let free: Vec<Pos> = grid::positions()
+                .filter(|&pos| {
+                    world.level[pos].terrain.passable() && world.level[pos].mob_id.is_none()
+                })
+                .collect();
+            if let Some(&to) = world.rng.choose(&free) {
+                let from = world[mob_id].pos;
+                relocate(mob_id, to, world);
+                world.events.push(Event::Teleported { mob_id, from, to });
+            }
+        }
+        Magic::MagicMap => {
+            // Only the player has a field of view to fill in
+            if mob_id.is_player() {
+                reveal_level(world);
+            }
+        }
+        Magic::Blink(distance) => {
+            let from = world[mob_id].pos;
+            let direction = world[mob_id].facing;
+            let mut to = from;
+            for _ in 0..distance {
+                let tile = &world.level[to + direction];
+                if !tile.terrain.passable() || tile.mob_id.is_some() {
+                    break;
+                }
+                to += direction;
+            }
+            relocate(mob_id, to, world);
+            world.events.push(Event::Teleported { mob_id, from, to });
+        }
+        Magic::Apply(effect, turns) => effect::apply(mob_id, effect, turns, None, world),
+    }
+}
+
+/// Marks every position on the level the player has not seen as remembered.
+pub(super) fn reveal_level(world: &mut World) {
+    for pos in grid::positions() {
+        if world.fov[pos] == TileView::None {
+            world.fov[pos] = TileView::Remembered(world.level[pos].terrain);
+            world.events.push(Event::Revealed(pos));
+        }
+    }
+}
+
+/// Moves a mob to a position without checking that it is free.
+fn relocate(mob_id: MobId, to: Pos, world: &mut World) {
+    let from = world[mob_id].pos;
+    world.level[from].mob_id = None;
+    world.level[to].mob_id = Some(mob_id);
+    world[mob_id].pos = to;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::tile::Terrain;
+    use world::action;
+    use world::item::{Item, Potion};
+    use world::mob::PLAYER_ID;
+
+    #[test]
+    fn test_blink_stops_at_wall() {
+        let mut world = World::from_seed(3);
+        let start = world.player.pos;
+        world.player.facing = Direction::East;
+        let mut pos = start;
+        for _ in 0..2 {
+            pos += Direction::East;
+            world.level[pos].terrain = Terrain::Floor;
+            world.level[pos].mob_id = None;
+        }
+        world.level[pos + Direction::East].terrain = Terrain::Wall;
+        cast(PLAYER_ID, Magic::Blink(4), &mut world);
+        assert_eq!(world.player.pos, pos);
+        assert_eq!(world.level[pos].mob_id, Some(PLAYER_ID));
+        assert_eq!(world.level[start].mob_id, None);
+    }
+
+    #[test]
+    fn test_heal_above_max_health() {
+        let mut world = World::from_seed(3);
+        world.player.health = world.player.max_health + 50;
+        cast(PLAYER_ID, Magic::Heal(5), &mut world);
+        assert_eq!(world.player.health, world.player.max_health + 50);
+    }
+
+    #[test]
+    fn test_swiftness_hastes() {
+        let mut world = World::from_seed(3);
+        world.player.inventory = vec![Item::Potion(Potion::Swiftness)];
+        action::consume(PLAYER_ID, 0, &mut world).unwrap();
+        assert!(world.player.has_effect(Effect::Haste));
+        assert_eq!(world.player.current_speed(), world.player.speed * 2);
+    }
+
+    #[test]
+    fn test_teleport_lands_somewhere_free() {
+        let mut world = World::from_seed(3);
+        cast(PLAYER_ID, Magic::Teleport, &mut world);
+        let pos = world.player.pos;
+        assert!(world.level[pos].terrain.passable());
+        assert_eq!(world.level[pos].mob_id, Some(PLAYER_ID));
+    }
+}
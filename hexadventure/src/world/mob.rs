@@ -1,22 +1,45 @@
 use prelude::*;
+use std::cmp;
 use std::ops::{Index, IndexMut};
+use world::combat::Shape;
+use world::effect::ActiveEffect;
+use world::event::Event;
+use world::faction::Faction;
+use world::item::{self, Equipment, Item};
+use world::stats::Stats;
+use world::status::{Death, Status};
 
 pub const PLAYER_ID: MobId = MobId {
     inner: InnerMobId::Player,
 };
 
 /// Represents a mob, or "moving object," i.e. the player or a monster
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Mob {
     pub pos: Pos,
     pub facing: Direction,
     pub species: Species,
+    pub faction: Faction,
     pub guard: u32,
     pub max_guard: u32,
     pub guard_recovery: u32,
+    /// The number of turns since the mob last fought
+    pub calm: u32,
+    /// Whether the mob will block the next hit from the front before its next turn
+    pub braced: bool,
     pub health: u32,
     pub max_health: u32,
     pub alive: bool,
+    /// How much energy the mob gains each time step, before slow and haste
+    pub speed: u32,
+    /// The mob may act whenever this is not negative
+    pub energy: i32,
+    /// Energy gained since the mob's effects and guard last ticked
+    pub clock: u32,
+    pub effects: Vec<ActiveEffect>,
+    /// Items carried, at most `item::INVENTORY_SIZE`
+    pub inventory: Vec<Item>,
+    pub equipment: Equipment,
 }
 
 /// The identity of a mob
@@ -25,81 +48,213 @@ pub struct Mob {
 /// interior mutability. Instead of using Cell or Refcell, this interior
 /// mutability is achieved with a central mob owner whose mutability follows
 /// regular borrow checker rules.
-#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct MobId {
     inner: InnerMobId,
 }
 
-/// A vector that owns all non-player mobs
+/// A vector that owns all non-player mobs on a level
 ///
 /// This struct wraps a vec in order to keep in private, preventing access
 /// of mobs without using their id.
-#[derive(Serialize, Deserialize)]
+///
+/// Dead mobs are removed and their slots are reused. Each slot counts how
+/// many times it has been emptied, so that ids of removed mobs can be told
+/// apart from ids of the mobs that replaced them.
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Npcs {
-    npcs: Vec<Mob>,
+    /// The depth of the level these mobs live on
+    depth: u32,
+    slots: Vec<Slot>,
+    /// Indices of empty slots
+    free: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
+struct Slot {
+    generation: u32,
+    mob: Option<Mob>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Species {
     Hero,
     Skeleton,
 }
 
 /// Identifies a mob
-#[derive(Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+///
+/// An npc is identified by its level, its slot in that level's Npcs, and
+/// the generation of that slot when it was inserted.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
 enum InnerMobId {
     Player,
-    Npc(usize),
+    Npc {
+        depth: u32,
+        index: usize,
+        generation: u32,
+    },
 }
 
 impl Mob {
     pub fn new(pos: Pos, species: Species) -> Self {
+        let stats = species.stats();
         Mob {
             pos,
             facing: Direction::East,
             species,
-            guard: 100,
-            max_guard: 100,
+            faction: stats.faction,
+            guard: stats.max_guard,
+            max_guard: stats.max_guard,
             guard_recovery: 0,
-            health: 100,
-            max_health: 100,
+            calm: 0,
+            braced: false,
+            health: stats.max_health,
+            max_health: stats.max_health,
             alive: true,
+            speed: stats.speed,
+            energy: 0,
+            clock: 0,
+            effects: Vec::new(),
+            inventory: Vec::new(),
+            equipment: Equipment::default(),
         }
     }
 }
 
+impl Mob {
+    /// Adds guard without going over the maximum, and returns how much was added.
+    pub fn restore_guard(&mut self, amount: u32) -> u32 {
+        let restored = cmp::min(amount, self.max_guard.saturating_sub(self.guard));
+        self.guard += restored;
+        restored
+    }
+
+    /// The stats of the mob's species with its equipment applied
+    pub fn stats(&self) -> Stats {
+        self.equipment
+            .items()
+            .filter_map(Item::equip_stats)
+            .fold(self.species.stats(), Stats::with)
+    }
+
+    /// Which hexes this mob's melee attacks hit.
+    pub fn attack_shape(&self) -> Shape {
+        self.stats().shape
+    }
+}
+
 impl MobId {
     pub fn is_player(&self) -> bool {
         match self.inner {
             InnerMobId::Player => true,
-            InnerMobId::Npc(_) => false,
+            InnerMobId::Npc { .. } => false,
         }
     }
 
-    pub fn die(self, world: &mut World) {
+    /// Whether this id refers to a living mob on the current level
+    pub fn is_alive(self, world: &World) -> bool {
+        match world.mob(self) {
+            Some(mob) => mob.alive,
+            None => false,
+        }
+    }
+
+    /// Kills a mob, removing it from the level
+    ///
+    /// The player is kept around so that the frontend can still show it,
+    /// but any other mob is dropped and its id becomes stale.
+    pub fn die(self, death: Death, world: &mut World) {
         let mob_pos = world[self].pos;
+        item::scatter(self, world);
         world.level[mob_pos].mob_id = None;
         world[self].alive = false;
-    }
-
-    fn new(index: usize) -> Self {
-        MobId {
-            inner: InnerMobId::Npc(index),
+        let species = world[self].species;
+        world.events.push(Event::Died {
+            mob_id: self,
+            species,
+        });
+        if self.is_player() {
+            world.status = Status::Dead(death);
+        } else {
+            world.npcs.remove(self);
         }
     }
 }
 
 impl Npcs {
-    /// Creates a new empty Npcs struct
-    pub fn new() -> Self {
-        Npcs { npcs: Vec::new() }
+    /// Creates a new empty Npcs struct for the level at the given depth
+    pub fn new(depth: u32) -> Self {
+        Npcs {
+            depth,
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
     }
 
     /// Inserts a new mob and returns its id
+    ///
+    /// Empty slots are filled before the vec grows.
     pub fn insert(&mut self, mob: Mob) -> MobId {
-        let id = MobId::new(self.npcs.len());
-        self.npcs.push(mob);
-        id
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    mob: None,
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.slots[index].mob = Some(mob);
+        self.id(index).unwrap()
+    }
+
+    /// Removes a mob, returning it if the id was not stale
+    pub fn remove(&mut self, id: MobId) -> Option<Mob> {
+        let index = self.index(id)?;
+        let mob = self.slots[index].mob.take();
+        self.slots[index].generation += 1;
+        self.free.push(index);
+        mob
+    }
+
+    pub fn get(&self, id: MobId) -> Option<&Mob> {
+        let index = self.index(id)?;
+        self.slots[index].mob.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: MobId) -> Option<&mut Mob> {
+        let index = self.index(id)?;
+        self.slots[index].mob.as_mut()
+    }
+
+    /// The id of the mob in a slot, if there is one
+    fn id(&self, index: usize) -> Option<MobId> {
+        let slot = self.slots.get(index)?;
+        slot.mob.as_ref().map(|_| MobId {
+            inner: InnerMobId::Npc {
+                depth: self.depth,
+                index,
+                generation: slot.generation,
+            },
+        })
+    }
+
+    /// The slot an id refers to, if the id is not stale
+    fn index(&self, id: MobId) -> Option<usize> {
+        match id.inner {
+            InnerMobId::Npc {
+                depth,
+                index,
+                generation,
+            } if depth == self.depth
+                && self.slots.get(index).map(|slot| slot.generation) == Some(generation) =>
+            {
+                Some(index)
+            }
+            _ => None,
+        }
     }
 }
 
@@ -113,9 +268,10 @@ where
     F: FnMut(MobId, &mut World),
 {
     let mut i = 0;
-    while i < world.npcs.npcs.len() {
-        let id = MobId::new(i);
-        f(id, world);
+    while i < world.npcs.slots.len() {
+        if let Some(id) = world.npcs.id(i) {
+            f(id, world);
+        }
         i += 1;
     }
 }
@@ -124,30 +280,68 @@ pub fn for_each<F>(world: &World, mut f: F)
 where
     F: FnMut(MobId),
 {
-    let mut i = 0;
-    while i < world.npcs.npcs.len() {
-        let id = MobId::new(i);
-        f(id);
-        i += 1;
+    for i in 0..world.npcs.slots.len() {
+        if let Some(id) = world.npcs.id(i) {
+            f(id);
+        }
     }
 }
 
+impl World {
+    /// Finds a mob, or returns None if the id is stale
+    pub fn mob(&self, id: MobId) -> Option<&Mob> {
+        match id.inner {
+            InnerMobId::Player => Some(&self.player),
+            InnerMobId::Npc { .. } => self.npcs.get(id),
+        }
+    }
+
+    /// Finds a mob, or returns None if the id is stale
+    pub fn mob_mut(&mut self, id: MobId) -> Option<&mut Mob> {
+        match id.inner {
+            InnerMobId::Player => Some(&mut self.player),
+            InnerMobId::Npc { .. } => self.npcs.get_mut(id),
+        }
+    }
+}
+
+/// Panics if the id is stale
 impl Index<MobId> for World {
     type Output = Mob;
 
     fn index(&self, id: MobId) -> &Mob {
-        match id.inner {
-            InnerMobId::Player => &self.player,
-            InnerMobId::Npc(index) => &self.npcs.npcs[index],
-        }
+        self.mob(id).expect("Stale mob id.")
     }
 }
 
+/// Panics if the id is stale
 impl IndexMut<MobId> for World {
     fn index_mut(&mut self, id: MobId) -> &mut Mob {
-        match id.inner {
-            InnerMobId::Player => &mut self.player,
-            InnerMobId::Npc(index) => &mut self.npcs.npcs[index],
-        }
+        self.mob_mut(id).expect("Stale mob id.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_ids() {
+        let mut npcs = Npcs::new(1);
+        let first = npcs.insert(Mob::new(grid::center(), Species::Skeleton));
+        let second = npcs.insert(Mob::new(grid::center(), Species::Skeleton));
+        assert!(npcs.remove(first).is_some());
+        assert!(npcs.get(first).is_none());
+        assert!(npcs.remove(first).is_none());
+
+        let third = npcs.insert(Mob::new(grid::center(), Species::Skeleton));
+        assert_eq!(npcs.slots.len(), 2);
+        assert!(third != first);
+        assert!(npcs.get(first).is_none());
+        assert!(npcs.get(second).is_some());
+        assert!(npcs.get(third).is_some());
+
+        let other_level = Npcs::new(2);
+        assert!(other_level.get(second).is_none());
     }
 }
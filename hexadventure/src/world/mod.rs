@@ -1,45 +1,110 @@
+use self::dungeon::{Dungeon, Level};
+use self::event::Event;
+use self::history::History;
+use self::item::{Appearances, Item};
 use self::mob::{Mob, Npcs, Species::Hero, PLAYER_ID};
+use self::replay::Replay;
+use self::status::Status;
 use fov::calc_fov;
 use level::place_mob;
 use level::tile::{Tile, TileView};
 use level::Architect;
 use prelude::*;
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, IsaacRng, Rng, SeedableRng};
+use std::vec::Drain;
 
 pub mod action;
 mod ai;
+pub mod combat;
+pub mod dungeon;
+pub mod effect;
+pub mod event;
+pub mod faction;
+pub mod history;
+pub mod item;
+pub mod magic;
 pub mod mob;
+pub mod replay;
 mod schedule;
+pub mod stats;
+pub mod status;
+#[cfg(feature = "wizard")]
+pub mod wizard;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct World {
     pub level: Grid<Tile>,
     pub fov: Grid<TileView>,
     pub player: Mob,
     npcs: Npcs,
-    architect: Architect,
+    depth: u32,
+    dungeon: Dungeon,
+    /// Source of all randomness in gameplay, so that a game can be replayed
+    rng: IsaacRng,
+    replay: Replay,
+    status: Status,
+    /// Whether the player ignores health damage, for debugging
+    invulnerable: bool,
+    appearances: Appearances,
+    /// Kinds of items the player has learned by using them
+    identified: Vec<Item>,
+    /// Events that frontends have not drained yet
+    #[serde(skip)]
+    events: Vec<Event>,
+    /// Snapshots for undo, which are not saved
+    #[serde(skip)]
+    history: History,
 }
 
 impl World {
+    /// Creates a world from a random seed.
     pub fn new() -> Self {
-        let seed = thread_rng().gen();
-        let mut architect = Architect::new(seed);
-        let (mut level, npcs) = architect.generate();
-        let player_pos = place_mob(&mut level, grid::center(), PLAYER_ID, &mut thread_rng());
+        World::from_seed(thread_rng().gen())
+    }
+
+    /// Creates a world that plays out identically for identical seeds and inputs.
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = IsaacRng::seed_from_u64(seed);
+        let architect = Architect::new(rng.gen());
+        let appearances = architect.appearances().clone();
+        let mut dungeon = Dungeon::new(architect);
+        let Level {
+            mut tiles,
+            fov,
+            npcs,
+        } = dungeon.take(1);
+        let player_pos = place_mob(&mut tiles, grid::center(), PLAYER_ID, &mut rng);
         let mut world = World {
-            level,
+            level: tiles,
             player: Mob::new(player_pos, Hero),
             npcs,
-            fov: Grid::new(|_| TileView::None),
-            architect,
+            fov,
+            depth: 1,
+            dungeon,
+            rng,
+            replay: Replay::new(seed),
+            status: Status::Playing,
+            invulnerable: false,
+            appearances,
+            identified: Vec::new(),
+            events: Vec::new(),
+            history: History::default(),
         };
         world.update_fov();
         world
     }
 
+    /// Removes and returns every event that happened since the last drain.
+    ///
+    /// Frontends should call this after each tick.
+    pub fn drain_events(&mut self) -> Drain<'_, Event> {
+        self.events.drain(..)
+    }
+
     fn update_fov(&mut self) {
         let level = &self.level;
         let fov = &mut self.fov;
+        let events = &mut self.events;
         for pos in grid::positions() {
             if fov[pos].is_visible() {
                 fov[pos] = TileView::Remembered(self.level[pos].terrain);
@@ -48,7 +113,44 @@ impl World {
         calc_fov(
             self.player.pos,
             |pos| level[pos].terrain.transparent(),
-            |pos| fov[pos] = TileView::Visible,
+            |pos| {
+                if fov[pos] == TileView::None {
+                    events.push(Event::Revealed(pos));
+                }
+                fov[pos] = TileView::Visible;
+            },
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid::DIRECTIONS;
+    use world::action;
+
+    fn play(seed: u64) -> World {
+        let mut world = World::from_seed(seed);
+        for i in 0..200 {
+            let result = if i % 7 == 0 {
+                action::rest(PLAYER_ID, &mut world)
+            } else {
+                action::walk(PLAYER_ID, DIRECTIONS[i % 13 % 6], &mut world)
+            };
+            if result.is_ok() {
+                world.tick();
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn test_same_seed_same_game() {
+        let a = play(7);
+        let b = play(7);
+        assert_eq!(a.player, b.player);
+        assert_eq!(a.npcs, b.npcs);
+        assert!(grid::positions().all(|pos| a.level[pos].terrain == b.level[pos].terrain));
+    }
+}
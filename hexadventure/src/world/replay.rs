@@ -0,0 +1,177 @@
+//! Recording and headless playback of games.
+//!
+//! A game is fully determined by its seed and the commands the player gave,
+//! so a replay only needs to store those.
+
+use bincode;
+use prelude::*;
+use world::action::{self, ActionResult};
+use world::item::Slot;
+use world::mob::PLAYER_ID;
+
+/// Something the player asked to do
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    Rest,
+    Turn(Direction),
+    Brace,
+    Walk(Direction),
+    Attack(Direction),
+    Shoot(Pos),
+    /// Throws the item at an index of the player's inventory at a position
+    Throw(usize, Pos),
+    Shove(Direction),
+    PickUp,
+    /// Drops the item at an index of the player's inventory
+    Drop(usize),
+    /// Equips the item at an index of the player's inventory
+    Equip(usize),
+    Unequip(Slot),
+    Harvest,
+    /// Uses up the item at an index of the player's inventory
+    Consume(usize),
+    Descend,
+    Ascend,
+}
+
+/// A seed and every command given since the world was created
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub commands: Vec<Command>,
+}
+
+/// The result of playing back a replay
+pub struct Playback {
+    pub world: World,
+    pub hash: u64,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Replay {
+            seed,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Plays every command against a fresh world without any frontend.
+    pub fn play(&self) -> Playback {
+        let mut world = World::from_seed(self.seed);
+        for &command in &self.commands {
+            let _ = world.command(command);
+            // Nothing is listening, so the events would only pile up
+            world.events.clear();
+        }
+        let hash = world.state_hash();
+        Playback { world, hash }
+    }
+}
+
+impl World {
+    /// Performs a player command, records it, and advances time if it succeeded.
+    ///
+    /// If undo is on, the world from before a successful command is kept.
+    pub fn command(&mut self, command: Command) -> ActionResult {
+        let snapshot = self.snapshot();
+        self.replay.commands.push(command);
+        let result = match command {
+            Command::Rest => action::rest(PLAYER_ID, self),
+            Command::Turn(direction) => action::turn(PLAYER_ID, direction, self),
+            Command::Brace => action::brace(PLAYER_ID, self),
+            Command::Walk(direction) => action::walk(PLAYER_ID, direction, self),
+            Command::Attack(direction) => action::attack(PLAYER_ID, direction, self),
+            Command::Shoot(target) => action::attack_ranged(PLAYER_ID, target, self),
+            Command::Throw(index, target) => action::throw(PLAYER_ID, index, target, self),
+            Command::Shove(direction) => action::shove(PLAYER_ID, direction, self),
+            Command::PickUp => action::pick_up(PLAYER_ID, self),
+            Command::Drop(index) => action::drop_item(PLAYER_ID, index, self),
+            Command::Equip(index) => action::equip(PLAYER_ID, index, self),
+            Command::Unequip(slot) => action::unequip(PLAYER_ID, slot, self),
+            Command::Harvest => action::harvest(PLAYER_ID, self),
+            Command::Consume(index) => action::consume(PLAYER_ID, index, self),
+            Command::Descend => action::descend(PLAYER_ID, self),
+            Command::Ascend => action::ascend(PLAYER_ID, self),
+        };
+        if result.is_ok() {
+            if let Some(snapshot) = snapshot {
+                self.push_snapshot(snapshot);
+            }
+            self.tick();
+        }
+        result
+    }
+
+    /// Everything recorded since the world was created
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Hashes the state of the current level, its mobs, the random number
+    /// generator and whether the game is over.
+    ///
+    /// Levels the player is not on are not included.
+    ///
+    /// Two games that played out the same way have the same hash, on any
+    /// platform and with any version of Rust, because the hash is FNV-1a over
+    /// the bincode encoding of that state.
+    pub fn state_hash(&self) -> u64 {
+        let state = (
+            self.depth,
+            &self.level,
+            &self.fov,
+            &self.player,
+            &self.npcs,
+            &self.rng,
+            &self.status,
+        );
+        let bytes = bincode::serialize(&state).expect("Failed to encode world state.");
+        fnv1a(&bytes)
+    }
+}
+
+/// The 64-bit FNV-1a hash of some bytes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use grid::DIRECTIONS;
+    use rand::Rng;
+
+    #[test]
+    fn test_playback_matches_recording() {
+        let mut world = World::from_seed(42);
+        for i in 0..100 {
+            let command = match i % 5 {
+                0 => Command::Rest,
+                4 => Command::Descend,
+                _ => Command::Walk(DIRECTIONS[i % 6]),
+            };
+            let _ = world.command(command);
+        }
+        let playback = world.replay().play();
+        assert_eq!(playback.hash, world.state_hash());
+        assert_eq!(playback.world.player, world.player);
+        assert_eq!(playback.world.replay(), world.replay());
+    }
+
+    #[test]
+    fn test_state_hash() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+
+        let mut world = World::from_seed(42);
+        let hash = world.state_hash();
+        assert_eq!(World::from_seed(42).state_hash(), hash);
+        let _: u32 = world.rng.gen();
+        assert_ne!(world.state_hash(), hash);
+    }
+}
@@ -1,16 +1,199 @@
+//! Decides which mob acts next.
+//!
+//! Every mob has an energy pool that is refilled by its speed each time step
+//! and drained by the cost of each action it takes. A mob may act whenever
+//! its energy is not negative, so a mob twice as fast as another gets to act
+//! twice as often.
+//!
+//! Effects and guard regeneration tick once for every `TURN_ENERGY` a mob
+//! gains, however many actions it spent that energy on, so that cheap actions
+//! don't make time pass faster.
+
 use prelude::*;
 use world::ai;
-use world::mob;
+use world::combat;
+use world::effect::{self, Effect, STUN_COST};
+use world::mob::{self, PLAYER_ID};
+
+/// Energy a mob gains between ticks of its effects and guard regeneration,
+/// which is the cost of most actions
+pub const TURN_ENERGY: u32 = 100;
 
 impl World {
+    /// Advances time until the player is ready to act again.
+    ///
+    /// Turns the player would take while stunned are skipped. A brace lasts
+    /// until the player's next turn.
     pub fn tick(&mut self) {
         self.update_fov();
         self.player.guard_recovery = 0;
+        loop {
+            while self.player.alive && self.player.energy < 0 {
+                self.step();
+            }
+            if !self.player.alive || !self.player.has_effect(Effect::Stun) {
+                break;
+            }
+            self.player.energy -= STUN_COST;
+        }
+        self.player.braced = false;
+    }
+
+    /// Gives every mob energy for one time step and lets the npcs act.
+    fn step(&mut self) {
+        gain_energy(PLAYER_ID, self);
         mob::for_each_mut(self, |mob_id, world| {
-            if world[mob_id].alive {
-                ai::act(mob_id, world).unwrap();
-                world[mob_id].guard_recovery = 0;
+            gain_energy(mob_id, world);
+            while mob_id.is_alive(world) && world[mob_id].energy >= 0 {
+                world[mob_id].braced = false;
+                if world[mob_id].has_effect(Effect::Stun) {
+                    world[mob_id].energy -= STUN_COST;
+                } else {
+                    ai::act(mob_id, world).unwrap();
+                }
+                if let Some(mob) = world.mob_mut(mob_id) {
+                    mob.guard_recovery = 0;
+                }
             }
         });
     }
 }
+
+/// Gives a mob energy for one time step, and ticks its effects and guard for
+/// every `TURN_ENERGY` it has gained.
+fn gain_energy(mob_id: MobId, world: &mut World) {
+    let speed = world[mob_id].current_speed();
+    world[mob_id].energy += speed as i32;
+    world[mob_id].clock += speed;
+    while mob_id.is_alive(world) && world[mob_id].clock >= TURN_ENERGY {
+        world[mob_id].clock -= TURN_ENERGY;
+        end_turn(mob_id, world);
+    }
+}
+
+/// Regenerates guard and counts down effects once a mob has had a turn's
+/// worth of time.
+fn end_turn(mob_id: MobId, world: &mut World) {
+    combat::regenerate(mob_id, world);
+    effect::end_turn(mob_id, world);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use level::tile::Terrain;
+    use world::action::{REST_COST, WALK_COST};
+    use world::event::Event;
+    use world::faction::Faction;
+    use world::mob::{Mob, Npcs, Species};
+    use world::replay::Command;
+
+    /// Spawns a stunned skeleton, whose remaining stun counts its turns.
+    fn spawn(speed: u32, world: &mut World) -> MobId {
+        let pos = grid::positions()
+            .find(|&pos| world.level[pos].terrain.passable() && world.level[pos].mob_id.is_none())
+            .unwrap();
+        let mob_id = world.npcs.insert(Mob::new(pos, Species::Skeleton));
+        world.level[pos].mob_id = Some(mob_id);
+        world[mob_id].speed = speed;
+        effect::apply(mob_id, Effect::Stun, 100, None, world);
+        mob_id
+    }
+
+    fn turns_taken(mob_id: MobId, world: &World) -> u32 {
+        100 - world[mob_id].effects[0].turns
+    }
+
+    /// Spawns a mob of a species walled in on its own, so that it only ever
+    /// rests.
+    fn spawn_walled(species: Species, world: &mut World) -> MobId {
+        let player_pos = world.player.pos;
+        let pos = grid::inner_positions()
+            .find(|&pos| pos.distance(player_pos) > 3 && pos.neighbors().all(grid::contains))
+            .unwrap();
+        world.level[pos].terrain = Terrain::Floor;
+        for neighbor in pos.neighbors() {
+            world.level[neighbor].terrain = Terrain::Wall;
+        }
+        let mob_id = world.npcs.insert(Mob::new(pos, species));
+        world.level[pos].mob_id = Some(mob_id);
+        world[mob_id].faction = Faction::Neutral;
+        mob_id
+    }
+
+    #[test]
+    fn test_species_speed() {
+        let mut world = World::from_seed(3);
+        world.npcs = Npcs::new(world.depth);
+        for pos in grid::positions() {
+            if world.level[pos].mob_id != Some(PLAYER_ID) {
+                world.level[pos].mob_id = None;
+            }
+        }
+        let hero = spawn_walled(Species::Hero, &mut world);
+        let skeleton = spawn_walled(Species::Skeleton, &mut world);
+        assert!(world[skeleton].speed < world[hero].speed);
+        world.drain_events();
+        let player_actions = 20;
+        for _ in 0..player_actions {
+            world.player.energy -= REST_COST;
+            world.tick();
+        }
+        let events: Vec<Event> = world.drain_events().collect();
+        let count = |mob_id: MobId| {
+            events
+                .iter()
+                .filter(|&&event| event == Event::Rested { mob_id })
+                .count()
+        };
+        assert!(count(hero) > count(skeleton));
+        assert!(count(skeleton) < player_actions);
+    }
+
+    #[test]
+    fn test_speed() {
+        let mut world = World::from_seed(3);
+        let speed = world.player.speed;
+        let fast = spawn(speed * 2, &mut world);
+        let normal = spawn(speed, &mut world);
+        let slow = spawn(speed / 2, &mut world);
+        for _ in 0..10 {
+            world.player.energy -= WALK_COST;
+            world.tick();
+        }
+        assert_eq!(turns_taken(fast, &world), 20);
+        assert_eq!(turns_taken(normal, &world), 10);
+        assert_eq!(turns_taken(slow, &world), 5);
+    }
+
+    #[test]
+    fn test_slow_mob_still_acts() {
+        let mut world = World::from_seed(3);
+        let crawler = spawn(1, &mut world);
+        effect::apply(crawler, Effect::Slow, 100, None, &mut world);
+        assert_eq!(world[crawler].current_speed(), 1);
+        world.player.energy -= WALK_COST;
+        world.tick();
+        // Losing a turn to the stun is still acting
+        assert!(world[crawler].energy < 0);
+    }
+
+    #[test]
+    fn test_time_passes_by_energy() {
+        let poisoned = |actions: &[Command]| {
+            let mut world = World::from_seed(3);
+            let pos = world.player.pos + Direction::East;
+            world.level[pos].terrain = Terrain::Floor;
+            world.level[pos].mob_id = None;
+            world.player.facing = Direction::East;
+            effect::apply(PLAYER_ID, Effect::Poison, 10, None, &mut world);
+            for &command in actions {
+                world.command(command).unwrap();
+            }
+            world.player.effects[0].turns
+        };
+        let rests = poisoned(&[Command::Rest, Command::Rest]);
+        assert_eq!(rests, poisoned(&[Command::Walk(Direction::East)]));
+        assert_eq!(rests, 9);
+    }
+}
@@ -0,0 +1,242 @@
+//! Combat statistics of each species and piece of equipment.
+//!
+//! Every species' stat block is defined in one table, so that a new monster
+//! only needs an entry there. Equipment is another table of changes made to
+//! those stat blocks.
+
+use rand::Rng;
+use world::combat::Shape;
+use world::effect::Effect;
+use world::faction::Faction;
+use world::item::{Item, Slot};
+use world::mob::Species;
+
+/// A number of dice with the same number of sides, like 2d6
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+}
+
+/// How a species fights
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Stats {
+    /// Damage dealt by each attack
+    pub attack: Dice,
+    /// Damage taken off every hit that gets through guard
+    pub armor: u32,
+    pub max_guard: u32,
+    pub max_health: u32,
+    /// Percentage of the damage taken from the front that can be recovered as
+    /// guard by retreating
+    pub guard_recovery: u32,
+    /// Guard regained by resting, and each turn spent out of combat
+    pub guard_regen: u32,
+    /// The energy gained each time step
+    ///
+    /// Most actions cost 100 energy, so a speed of 10 means one action
+    /// every 10 time steps.
+    pub speed: u32,
+    /// How the mob shoots, if it has anything to shoot with
+    pub ranged: Option<Ranged>,
+    /// Which hexes a melee attack hits, unless a weapon says otherwise
+    pub shape: Shape,
+    /// An effect and its duration put on anything this species wounds
+    pub on_hit: Option<(Effect, u32)>,
+    /// How many hexes a shove from this species pushes
+    pub push: u32,
+    /// Whether being pushed into water kills rather than stuns
+    pub drowns: bool,
+    /// The side mobs of this species start out on
+    pub faction: Faction,
+}
+
+/// How hard and how far something shot or thrown hits
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Ranged {
+    pub attack: Dice,
+    pub range: u32,
+}
+
+/// How a piece of equipment changes the stats of whoever has it equipped
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct EquipStats {
+    pub slot: Slot,
+    /// Replaces the attack of the wielder
+    pub attack: Option<Dice>,
+    /// Replaces the attack shape of the wielder
+    pub shape: Option<Shape>,
+    /// Lets the wielder shoot
+    pub ranged: Option<Ranged>,
+    /// Added to the armor of the wearer
+    pub armor: u32,
+    /// Added to the maximum guard of the wearer
+    pub max_guard: u32,
+    /// Replaces the effect the wielder puts on anything it wounds
+    pub on_hit: Option<(Effect, u32)>,
+    /// Taken off the guard recovery percentage of the wearer
+    pub recovery_penalty: u32,
+}
+
+impl Stats {
+    /// Applies the changes a piece of equipment makes.
+    pub fn with(self, equip: EquipStats) -> Stats {
+        Stats {
+            attack: equip.attack.unwrap_or(self.attack),
+            shape: equip.shape.unwrap_or(self.shape),
+            ranged: equip.ranged.or(self.ranged),
+            armor: self.armor + equip.armor,
+            max_guard: self.max_guard + equip.max_guard,
+            on_hit: equip.on_hit.or(self.on_hit),
+            guard_recovery: self.guard_recovery.saturating_sub(equip.recovery_penalty),
+            ..self
+        }
+    }
+}
+
+impl Dice {
+    pub fn roll<R: Rng>(self, rng: &mut R) -> u32 {
+        (0..self.count)
+            .map(|_| rng.gen_range(1, self.sides + 1))
+            .sum()
+    }
+
+    /// Every possible total and its probability, from lowest to highest
+    pub fn distribution(self) -> Vec<(u32, f64)> {
+        // ways[total] is the number of ways to roll that total
+        let mut ways = vec![1u64];
+        for _ in 0..self.count {
+            let mut next = vec![0; ways.len() + self.sides as usize];
+            for (total, &count) in ways.iter().enumerate() {
+                for side in 1..=self.sides as usize {
+                    next[total + side] += count;
+                }
+            }
+            ways = next;
+        }
+        let outcomes = (self.sides as f64).powi(self.count as i32);
+        ways.into_iter()
+            .enumerate()
+            .filter(|&(_, count)| count > 0)
+            .map(|(total, count)| (total as u32, count as f64 / outcomes))
+            .collect()
+    }
+
+    pub fn min(self) -> u32 {
+        self.count
+    }
+
+    pub fn max(self) -> u32 {
+        self.count * self.sides
+    }
+}
+
+impl Species {
+    pub fn stats(self) -> Stats {
+        match self {
+            Species::Hero => Stats {
+                attack: Dice { count: 2, sides: 6 },
+                armor: 0,
+                max_guard: 100,
+                max_health: 100,
+                guard_recovery: 50,
+                guard_regen: 2,
+                speed: 10,
+                ranged: None,
+                shape: Shape::Single,
+                on_hit: None,
+                push: 2,
+                drowns: false,
+                faction: Faction::Allied,
+            },
+            Species::Skeleton => Stats {
+                attack: Dice { count: 2, sides: 6 },
+                armor: 1,
+                max_guard: 40,
+                max_health: 30,
+                guard_recovery: 50,
+                guard_regen: 1,
+                speed: 8,
+                ranged: None,
+                shape: Shape::Single,
+                on_hit: Some((Effect::Bleeding, 3)),
+                push: 1,
+                drowns: true,
+                faction: Faction::Hostile,
+            },
+        }
+    }
+}
+
+impl Item {
+    /// How the item changes the stats of a mob, if it can be equipped
+    pub fn equip_stats(self) -> Option<EquipStats> {
+        let weapon = |attack, shape| EquipStats {
+            slot: Slot::Weapon,
+            attack: Some(attack),
+            shape: Some(shape),
+            ranged: None,
+            armor: 0,
+            max_guard: 0,
+            on_hit: None,
+            recovery_penalty: 0,
+        };
+        let armor = |slot, armor, max_guard, recovery_penalty| EquipStats {
+            slot,
+            attack: None,
+            shape: None,
+            ranged: None,
+            armor,
+            max_guard,
+            on_hit: None,
+            recovery_penalty,
+        };
+        Some(match self {
+            Item::Spear => weapon(Dice { count: 2, sides: 6 }, Shape::Reach),
+            Item::Axe => weapon(Dice { count: 2, sides: 6 }, Shape::Sweep),
+            Item::Rapier => weapon(Dice { count: 3, sides: 4 }, Shape::Lunge),
+            Item::Dagger => EquipStats {
+                on_hit: Some((Effect::Poison, 4)),
+                ..weapon(Dice { count: 1, sides: 6 }, Shape::Single)
+            },
+            Item::Bow => EquipStats {
+                ranged: Some(Ranged {
+                    attack: Dice { count: 2, sides: 6 },
+                    range: 6,
+                }),
+                ..weapon(Dice { count: 1, sides: 4 }, Shape::Single)
+            },
+            Item::Jerkin => armor(Slot::Armor, 1, 0, 0),
+            Item::Cuirass => armor(Slot::Armor, 3, 0, 25),
+            Item::Shield => armor(Slot::Shield, 0, 30, 0),
+            Item::Javelin | Item::Brownberry | Item::Potion(_) | Item::Scroll(_) => return None,
+        })
+    }
+
+    /// How the item hits when thrown, if it is made for throwing
+    pub fn throw_stats(self) -> Option<Ranged> {
+        match self {
+            Item::Javelin => Some(Ranged {
+                attack: Dice { count: 2, sides: 6 },
+                range: 4,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{IsaacRng, SeedableRng};
+
+    #[test]
+    fn test_dice() {
+        let mut rng = IsaacRng::from_seed([0; 32]);
+        let dice = Dice { count: 3, sides: 4 };
+        for _ in 0..100 {
+            let roll = dice.roll(&mut rng);
+            assert!(dice.min() <= roll && roll <= dice.max());
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Whether the game is over, and how it ended.
+
+use prelude::*;
+use world::effect::Effect;
+use world::mob::Species;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Status {
+    Playing,
+    Dead(Death),
+}
+
+/// How a mob died
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Death {
+    pub cause: Cause,
+    /// The species of whoever dealt the killing blow, if anyone did
+    pub killer: Option<Species>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Cause {
+    Melee,
+    Ranged,
+    Effect(Effect),
+    /// Shoved into something solid
+    Collision,
+    /// Shoved into deep water
+    Drowned,
+    /// Killed by a debugging command
+    Wizard,
+}
+
+impl World {
+    pub fn status(&self) -> Status {
+        self.status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use world::action::{self, ActionError};
+    use world::mob::{self, PLAYER_ID};
+
+    #[test]
+    fn test_player_death() {
+        let mut world = World::from_seed(3);
+        let mut skeleton = None;
+        mob::for_each(&world, |mob_id| skeleton = skeleton.or(Some(mob_id)));
+        let skeleton = skeleton.unwrap();
+        let player_pos = world.player.pos;
+        let direction = grid::DIRECTIONS
+            .iter()
+            .cloned()
+            .find(|&direction| {
+                let pos = player_pos + direction;
+                world.level[pos].terrain.passable() && world.level[pos].mob_id.is_none()
+            })
+            .unwrap();
+        let old_pos = world[skeleton].pos;
+        world.level[old_pos].mob_id = None;
+        world.level[player_pos + direction].mob_id = Some(skeleton);
+        world[skeleton].pos = player_pos + direction;
+        world.player.guard = 0;
+        world.player.health = 1;
+
+        action::walk(skeleton, direction.rotate(3), &mut world).unwrap();
+        assert_eq!(
+            world.status(),
+            Status::Dead(Death {
+                cause: Cause::Melee,
+                killer: Some(world[skeleton].species),
+            })
+        );
+        assert_eq!(action::rest(PLAYER_ID, &mut world), Err(ActionError::Dead));
+    }
+}
@@ -0,0 +1,223 @@
+//! Debugging commands that bypass the rules of the game.
+//!
+//! Only available with the `wizard` feature. Nothing done here is recorded
+//! in the replay, so a replay of a game that used these will not match.
+
+use level::place_mob;
+use level::tile::Terrain;
+use prelude::*;
+use rand::Rng;
+use std::cmp;
+use world::action::ActionError;
+use world::event::Event;
+use world::faction::Faction;
+use world::item::{self, Item};
+use world::magic;
+use world::mob::{Species, PLAYER_ID};
+use world::status::{Cause, Death};
+
+/// Borrows a world to run wizard commands on it
+pub struct Wizard<'a> {
+    world: &'a mut World,
+}
+
+impl World {
+    pub fn wizard(&mut self) -> Wizard<'_> {
+        Wizard { world: self }
+    }
+}
+
+impl<'a> Wizard<'a> {
+    /// Marks every position on the level as remembered.
+    pub fn reveal_level(&mut self) {
+        magic::reveal_level(self.world);
+    }
+
+    /// Moves the player to a free, passable position.
+    pub fn teleport(&mut self, pos: Pos) -> Result<(), ActionError> {
+        let world = &mut *self.world;
+        check_free(pos, world)?;
+        let old_pos = world.player.pos;
+        world.level[old_pos].mob_id = None;
+        world.level[pos].mob_id = Some(PLAYER_ID);
+        world.player.pos = pos;
+        world.events.push(Event::Moved {
+            mob_id: PLAYER_ID,
+            from: old_pos,
+            to: pos,
+        });
+        world.update_fov();
+        Ok(())
+    }
+
+    /// Puts an item on the floor at or next to a position, and returns where
+    /// it landed.
+    pub fn create_item(&mut self, item: Item, pos: Pos) -> Option<Pos> {
+        item::place(pos, item, self.world)
+    }
+
+    /// Puts a random kind of item on the floor at or next to a position.
+    pub fn create_random_item(&mut self, pos: Pos) -> Option<(Item, Pos)> {
+        let item = *self.world.rng.choose(&Item::kinds()).unwrap();
+        self.create_item(item, pos).map(|pos| (item, pos))
+    }
+
+    /// Creates a new mob at a free, passable position.
+    pub fn spawn(&mut self, species: Species, pos: Pos) -> Result<MobId, ActionError> {
+        let world = &mut *self.world;
+        check_free(pos, world)?;
+        let mob_id = world.npcs.insert(Mob::new(pos, species));
+        world.level[pos].mob_id = Some(mob_id);
+        Ok(mob_id)
+    }
+
+    /// Sets the health of a mob, up to its maximum. A mob set to no health
+    /// dies.
+    pub fn set_health(&mut self, mob_id: MobId, health: u32) {
+        let world = &mut *self.world;
+        world[mob_id].health = cmp::min(health, world[mob_id].max_health);
+        if health == 0 {
+            let death = Death {
+                cause: Cause::Wizard,
+                killer: None,
+            };
+            mob_id.die(death, world);
+        }
+    }
+
+    /// Sets the guard of a mob, up to its maximum.
+    pub fn set_guard(&mut self, mob_id: MobId, guard: u32) {
+        let max_guard = self.world[mob_id].max_guard;
+        self.world[mob_id].guard = cmp::min(guard, max_guard);
+    }
+
+    pub fn set_faction(&mut self, mob_id: MobId, faction: Faction) {
+        self.world[mob_id].faction = faction;
+    }
+
+    /// Moves the player to the level at a depth, starting from 1.
+    ///
+    /// Any levels between the current level and the new one are generated
+    /// without being visited.
+    pub fn jump_to_depth(&mut self, depth: u32) {
+        let world = &mut *self.world;
+        if depth == 0 || depth == world.depth {
+            return;
+        }
+        let old_pos = world.player.pos;
+        world.level[old_pos].mob_id = None;
+        world.change_level(depth);
+        world.player.pos = place_mob(&mut world.level, old_pos, PLAYER_ID, &mut world.rng);
+        world.update_fov();
+    }
+
+    /// Turns invulnerability on or off and returns whether it is now on.
+    ///
+    /// An invulnerable player loses guard as usual but never loses health.
+    pub fn toggle_invulnerable(&mut self) -> bool {
+        self.world.invulnerable = !self.world.invulnerable;
+        self.world.invulnerable
+    }
+}
+
+fn check_free(pos: Pos, world: &World) -> Result<(), ActionError> {
+    if !grid::contains(pos) {
+        Err(ActionError::Impassable(Terrain::Wall))
+    } else if !world.level[pos].terrain.passable() {
+        Err(ActionError::Impassable(world.level[pos].terrain))
+    } else if world.level[pos].mob_id.is_some() {
+        Err(ActionError::Occupied)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use world::action;
+    use world::status::Status;
+
+    /// A free, passable position next to the player
+    fn free_neighbor(world: &mut World) -> Pos {
+        let pos = world.player.pos + Direction::East;
+        world.level[pos].terrain = Terrain::Floor;
+        world.level[pos].mob_id = None;
+        pos
+    }
+
+    #[test]
+    fn test_teleport() {
+        let mut world = World::from_seed(3);
+        let pos = free_neighbor(&mut world) + Direction::East;
+        world.level[pos].terrain = Terrain::Wall;
+        assert_eq!(
+            world.wizard().teleport(pos),
+            Err(ActionError::Impassable(Terrain::Wall))
+        );
+        world.level[pos].terrain = Terrain::Floor;
+        let old_pos = world.player.pos;
+        world.wizard().teleport(pos).unwrap();
+        assert_eq!(world.player.pos, pos);
+        assert_eq!(world.level[pos].mob_id, Some(PLAYER_ID));
+        assert_eq!(world.level[old_pos].mob_id, None);
+    }
+
+    #[test]
+    fn test_spawn() {
+        let mut world = World::from_seed(3);
+        let pos = free_neighbor(&mut world);
+        let skeleton = world.wizard().spawn(Species::Skeleton, pos).unwrap();
+        assert!(skeleton.is_alive(&world));
+        assert_eq!(world.level[pos].mob_id, Some(skeleton));
+        assert_eq!(
+            world.wizard().spawn(Species::Skeleton, pos),
+            Err(ActionError::Occupied)
+        );
+    }
+
+    #[test]
+    fn test_jump_to_depth() {
+        let mut world = World::from_seed(3);
+        world.wizard().jump_to_depth(3);
+        assert_eq!(world.depth, 3);
+        let pos = world.player.pos;
+        assert_eq!(world.level[pos].mob_id, Some(PLAYER_ID));
+        assert!(world.fov[pos].is_visible());
+    }
+
+    #[test]
+    fn test_invulnerable() {
+        let mut world = World::from_seed(3);
+        let pos = free_neighbor(&mut world);
+        let skeleton = world.wizard().spawn(Species::Skeleton, pos).unwrap();
+        world.wizard().set_faction(skeleton, Faction::Hostile);
+        assert!(world.wizard().toggle_invulnerable());
+        world.wizard().set_guard(PLAYER_ID, 0);
+        let health = world.player.health;
+        for _ in 0..5 {
+            action::attack(skeleton, Direction::West, &mut world).unwrap();
+        }
+        assert_eq!(world.player.health, health);
+    }
+
+    #[test]
+    fn test_set_health() {
+        let mut world = World::from_seed(3);
+        world.wizard().set_health(PLAYER_ID, 1000);
+        assert_eq!(world.player.health, world.player.max_health);
+        world.wizard().set_guard(PLAYER_ID, 1000);
+        assert_eq!(world.player.guard, world.player.max_guard);
+
+        let pos = free_neighbor(&mut world);
+        let skeleton = world.wizard().spawn(Species::Skeleton, pos).unwrap();
+        world.wizard().set_health(skeleton, 0);
+        assert!(!skeleton.is_alive(&world));
+        assert_eq!(world.level[pos].mob_id, None);
+        world.wizard().set_health(PLAYER_ID, 0);
+        match world.status() {
+            Status::Dead(death) => assert_eq!(death.cause, Cause::Wizard),
+            Status::Playing => panic!("The player should be dead"),
+        }
+    }
+}
@@ -7,7 +7,9 @@ use app_dirs::{app_root, AppDataType, AppInfo};
 extern crate ggez;
 use ggez::conf::{Conf, WindowMode, WindowSetup};
 use ggez::event;
-use ggez::event::{EventHandler, Keycode, Mod};
+#[cfg(feature = "wizard")]
+use ggez::event::MouseButton;
+use ggez::event::{EventHandler, Keycode, Mod, LALTMOD, LSHIFTMOD, RALTMOD, RSHIFTMOD};
 use ggez::graphics;
 use ggez::graphics::spritebatch::SpriteBatch;
 use ggez::graphics::{Color, DrawParam, Point2};
@@ -19,18 +21,33 @@ extern crate hexadventure;
 use hexadventure::grid::{pos_to_location, Location};
 use hexadventure::level::tile::TileView;
 use hexadventure::prelude::*;
-use hexadventure::world::action;
-use hexadventure::world::mob::PLAYER_ID;
+use hexadventure::world::event::Event;
+use hexadventure::world::faction;
+use hexadventure::world::item::Slot;
+use hexadventure::world::mob::{self, PLAYER_ID};
+use hexadventure::world::replay::{Command, Replay};
+use hexadventure::world::status::Status;
 
 mod sprite;
-use sprite::{color_from_tile, darken, sprite_from_species, sprite_src, Sprite};
+use sprite::{
+    color_from_item, color_from_tile, darken, sprite_from_item, sprite_from_species, sprite_src,
+    Sprite,
+};
+
+mod message;
+use message::{a_item, describe_death, describe_error, describe_event, Log};
 
 mod side;
 
+#[cfg(feature = "wizard")]
+mod wizard;
+
 use std::error::Error;
-use std::fs::File;
+use std::fs::{self, File};
+use std::mem;
 
 const SAVE_NAME: &str = "save.bincode";
+const REPLAY_NAME: &str = "replay.bincode";
 const APP_INFO: AppInfo = AppInfo {
     name: "hex-adventure",
     author: "as-f",
@@ -44,12 +61,35 @@ enum Arrow {
     Right { diagonal: bool },
 }
 
+/// What the next key press answers
+#[derive(Copy, Clone, PartialEq)]
+enum Prompt {
+    None,
+    /// A direction to turn toward
+    Turn,
+    /// An inventory slot to drop
+    Drop,
+    /// An inventory slot to equip
+    Equip,
+    /// An equipment slot to empty
+    Unequip,
+    /// An inventory slot to use up
+    Consume,
+    /// An inventory slot to throw at the nearest target
+    Throw,
+}
+
 struct MainState {
     world: World,
     spritebatch: SpriteBatch,
     redraw: bool,
     dests: Grid<Point2>,
     pressed_arrow: Arrow,
+    prompt: Prompt,
+    log: Log,
+    /// Whether the wizard mode modifier is held down
+    #[cfg(feature = "wizard")]
+    wizard_held: bool,
 }
 
 fn pos_to_point2(pos: Pos) -> Point2 {
@@ -74,6 +114,10 @@ impl MainState {
             redraw: true,
             dests,
             pressed_arrow: Arrow::None,
+            prompt: Prompt::None,
+            log: Log::new(),
+            #[cfg(feature = "wizard")]
+            wizard_held: false,
         }
     }
 }
@@ -94,7 +138,108 @@ fn save_world(game: &World) -> Result<(), Box<Error>> {
     Ok(())
 }
 
+fn delete_save() -> Result<(), Box<Error>> {
+    let mut path = app_root(AppDataType::UserData, &APP_INFO)?;
+    path.push(SAVE_NAME);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn save_replay(replay: &Replay) -> Result<(), Box<Error>> {
+    let mut path = app_root(AppDataType::UserData, &APP_INFO)?;
+    path.push(REPLAY_NAME);
+    let file = File::create(path)?;
+    serialize_into(file, replay)?;
+    Ok(())
+}
+
+/// Plays back a replay file without opening a window.
+fn play_replay(path: &str) -> Result<(), Box<Error>> {
+    let file = File::open(path)?;
+    let replay: Replay = deserialize_from(file)?;
+    let playback = replay.play();
+    let player = &playback.world.player;
+    println!("Seed: {}", replay.seed);
+    println!("Commands: {}", replay.commands.len());
+    println!(
+        "Player: {:?} facing {:?}, health {}, guard {}",
+        player.pos, player.facing, player.health, player.guard
+    );
+    println!("State hash: {:016x}", playback.hash);
+    Ok(())
+}
+
 impl MainState {
+    fn command(&mut self, command: Command) {
+        if self.world.status() != Status::Playing {
+            return;
+        }
+        if let Err(error) = self.world.command(command) {
+            self.log.push(describe_error(error).to_owned());
+        }
+        self.drain_events();
+        if let Status::Dead(death) = self.world.status() {
+            self.log.push(describe_death(death));
+            self.log.push("Press enter to retry.".to_owned());
+        }
+    }
+
+    /// Finds the closest mob the player can see and is hostile to.
+    ///
+    /// Allies and neutral mobs are never picked, so that they are only ever
+    /// attacked on purpose.
+    fn nearest_target(&self) -> Option<Pos> {
+        let player_pos = self.world.player.pos;
+        let mut targets = Vec::new();
+        mob::for_each(&self.world, |mob_id| {
+            if faction::is_hostile(PLAYER_ID, mob_id, &self.world) {
+                targets.push(self.world[mob_id].pos);
+            }
+        });
+        targets
+            .into_iter()
+            .filter(|&pos| self.world.fov[pos].is_visible())
+            .min_by_key(|&pos| pos.distance(player_pos))
+    }
+
+    /// Asks the player a question answered by the next key press.
+    fn ask(&mut self, prompt: Prompt, question: &str) {
+        self.prompt = prompt;
+        self.log.push(question.to_owned());
+        self.redraw = true;
+    }
+
+    fn drain_events(&mut self) {
+        let events: Vec<Event> = self.world.drain_events().collect();
+        for event in events {
+            if let Some(message) = describe_event(&event, &self.world) {
+                self.log.push(message);
+            }
+            match event {
+                Event::Moved { mob_id, to, .. } if mob_id.is_player() => {
+                    if let Some(item) = self.world.level[to].item {
+                        self.log
+                            .push(format!("You see {}.", a_item(item, &self.world)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.redraw = true;
+    }
+
+    /// Finds the position drawn under a point on the screen.
+    #[cfg(feature = "wizard")]
+    fn point_to_pos(&self, x: f32, y: f32) -> Option<Pos> {
+        let distance = |pos: Pos| {
+            let dest = self.dests[pos];
+            (dest.x + 8.0 - x).powi(2) + (dest.y + 15.0 - y).powi(2)
+        };
+        grid::positions().find(|&pos| distance(pos) < 8.0 * 8.0)
+    }
+
     fn draw_tile(&mut self, sprite: Sprite, pos: Pos, color: Color, flip: bool) {
         self.spritebatch.add(DrawParam {
             src: sprite_src(sprite),
@@ -131,6 +276,7 @@ impl EventHandler for MainState {
             ctx,
             Point2::new((grid::WIDTH * 18 + 9) as f32, 0.0),
             &self.world,
+            self.log.messages(),
             &mut self.spritebatch,
         )?;
         for pos in grid::positions() {
@@ -143,6 +289,13 @@ impl EventHandler for MainState {
                             Direction::East | Direction::Northeast | Direction::Southeast => true,
                         };
                         self.draw_tile(sprite, pos, graphics::WHITE, flip);
+                    } else if let Some(item) = self.world.level[pos].item {
+                        self.draw_tile(
+                            sprite_from_item(item),
+                            pos,
+                            color_from_item(item, self.world.appearances()),
+                            false,
+                        );
                     } else {
                         let terrain = self.world.level[pos].terrain;
                         self.draw_tile(Sprite::from(terrain), pos, color_from_tile(terrain), false);
@@ -162,31 +315,109 @@ impl EventHandler for MainState {
         Ok(())
     }
 
-    fn key_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        keycode: Keycode,
-        _keymod: Mod,
-        _repeat: bool,
-    ) {
-        let action = match keycode {
-            Keycode::W => Some(Action::Walk(Direction::Northwest)),
-            Keycode::E => Some(Action::Walk(Direction::Northeast)),
-            Keycode::A => Some(Action::Walk(Direction::West)),
-            Keycode::D => Some(Action::Walk(Direction::East)),
-            Keycode::Z => Some(Action::Walk(Direction::Southwest)),
-            Keycode::X => Some(Action::Walk(Direction::Southeast)),
-            Keycode::S => Some(Action::Rest),
+    fn key_down_event(&mut self, _ctx: &mut Context, keycode: Keycode, keymod: Mod, _repeat: bool) {
+        #[cfg(feature = "wizard")]
+        {
+            if wizard::is_wizard_key(keycode) {
+                self.wizard_held = true;
+            }
+            if wizard::is_wizard_mod(keymod) {
+                if wizard::key_down(&mut self.world, keycode, &mut self.log) {
+                    self.drain_events();
+                }
+                return;
+            }
+        }
+        if self.world.status() != Status::Playing {
+            if keycode == Keycode::Return {
+                self.world = World::new();
+                self.log = Log::new();
+                self.redraw = true;
+            }
+            return;
+        }
+        let prompt = mem::replace(&mut self.prompt, Prompt::None);
+        if let Some(index) = slot(keycode) {
+            match prompt {
+                Prompt::Drop => self.command(Command::Drop(index)),
+                Prompt::Equip => self.command(Command::Equip(index)),
+                Prompt::Consume => self.command(Command::Consume(index)),
+                Prompt::Throw => match self.nearest_target() {
+                    Some(target) => self.command(Command::Throw(index, target)),
+                    None => {
+                        self.log.push("Nothing to throw at.".to_owned());
+                        self.redraw = true;
+                    }
+                },
+                _ => {}
+            }
+            return;
+        }
+        if prompt == Prompt::Unequip {
+            let slot = match keycode {
+                Keycode::W => Slot::Weapon,
+                Keycode::A => Slot::Armor,
+                Keycode::S => Slot::Shield,
+                _ => return,
+            };
+            self.command(Command::Unequip(slot));
+            return;
+        }
+        let command = match keycode {
+            Keycode::W => Some(direction_command(prompt, Direction::Northwest, keymod)),
+            Keycode::E => Some(direction_command(prompt, Direction::Northeast, keymod)),
+            Keycode::A => Some(direction_command(prompt, Direction::West, keymod)),
+            Keycode::D => Some(direction_command(prompt, Direction::East, keymod)),
+            Keycode::Z => Some(direction_command(prompt, Direction::Southwest, keymod)),
+            Keycode::X => Some(direction_command(prompt, Direction::Southeast, keymod)),
+            Keycode::S => Some(Command::Rest),
+            Keycode::Q => {
+                self.ask(Prompt::Turn, "Turn which way?");
+                None
+            }
+            Keycode::B => Some(Command::Brace),
+            Keycode::G => Some(Command::PickUp),
+            Keycode::R => {
+                self.ask(Prompt::Drop, "Drop which item?");
+                None
+            }
+            Keycode::H => Some(Command::Harvest),
+            Keycode::C => {
+                self.ask(Prompt::Consume, "Use which item?");
+                None
+            }
+            Keycode::T => {
+                self.ask(Prompt::Equip, "Equip which item?");
+                None
+            }
+            Keycode::U => {
+                self.ask(Prompt::Unequip, "Remove which? W/A/S");
+                None
+            }
+            Keycode::F => {
+                let target = self.nearest_target();
+                if target.is_none() {
+                    self.log.push("Nothing to shoot.".to_owned());
+                    self.redraw = true;
+                }
+                target.map(Command::Shoot)
+            }
+            Keycode::V => {
+                self.ask(Prompt::Throw, "Throw which item?");
+                None
+            }
+            Keycode::Period => Some(Command::Descend),
+            Keycode::Comma => Some(Command::Ascend),
             Keycode::Up => {
                 let (action, pressed_arrow) = match self.pressed_arrow {
                     Arrow::None | Arrow::Up => (None, Arrow::Up),
                     Arrow::Down => (None, Arrow::None),
                     Arrow::Left { .. } => (
-                        Some(Action::Walk(Direction::Northwest)),
+                        Some(Command::Walk(Direction::Northwest)),
                         Arrow::Left { diagonal: true },
                     ),
                     Arrow::Right { .. } => (
-                        Some(Action::Walk(Direction::Northeast)),
+                        Some(Command::Walk(Direction::Northeast)),
                         Arrow::Right { diagonal: true },
                     ),
                 };
@@ -198,11 +429,11 @@ impl EventHandler for MainState {
                     Arrow::None | Arrow::Down => (None, Arrow::Down),
                     Arrow::Up => (None, Arrow::None),
                     Arrow::Left { .. } => (
-                        Some(Action::Walk(Direction::Southwest)),
+                        Some(Command::Walk(Direction::Southwest)),
                         Arrow::Left { diagonal: true },
                     ),
                     Arrow::Right { .. } => (
-                        Some(Action::Walk(Direction::Southeast)),
+                        Some(Command::Walk(Direction::Southeast)),
                         Arrow::Right { diagonal: true },
                     ),
                 };
@@ -214,8 +445,8 @@ impl EventHandler for MainState {
                     Arrow::None => (None, Arrow::Left { diagonal: false }),
                     Arrow::Left { diagonal } => (None, Arrow::Left { diagonal }),
                     Arrow::Right { .. } => (None, Arrow::None),
-                    Arrow::Up => (Some(Action::Walk(Direction::Northwest)), Arrow::Up),
-                    Arrow::Down => (Some(Action::Walk(Direction::Southwest)), Arrow::Down),
+                    Arrow::Up => (Some(Command::Walk(Direction::Northwest)), Arrow::Up),
+                    Arrow::Down => (Some(Command::Walk(Direction::Southwest)), Arrow::Down),
                 };
                 self.pressed_arrow = pressed_arrow;
                 action
@@ -225,28 +456,27 @@ impl EventHandler for MainState {
                     Arrow::None => (None, Arrow::Right { diagonal: false }),
                     Arrow::Right { diagonal } => (None, Arrow::Right { diagonal }),
                     Arrow::Left { .. } => (None, Arrow::None),
-                    Arrow::Up => (Some(Action::Walk(Direction::Northeast)), Arrow::Up),
-                    Arrow::Down => (Some(Action::Walk(Direction::Southeast)), Arrow::Down),
+                    Arrow::Up => (Some(Command::Walk(Direction::Northeast)), Arrow::Up),
+                    Arrow::Down => (Some(Command::Walk(Direction::Southeast)), Arrow::Down),
                 };
                 self.pressed_arrow = pressed_arrow;
                 action
             }
             _ => None,
         };
-        if let Some(action) = action {
-            let success = match action {
-                Action::Rest => action::rest(PLAYER_ID, &mut self.world),
-                Action::Walk(direction) => action::walk(PLAYER_ID, direction, &mut self.world),
-            };
-            if success.is_ok() {
-                self.world.tick();
-            }
-            self.redraw = true;
+        if let Some(command) = command {
+            self.command(command);
         }
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, keycode: Keycode, _keymod: Mod, _repeat: bool) {
-        let action = match keycode {
+        #[cfg(feature = "wizard")]
+        {
+            if wizard::is_wizard_key(keycode) {
+                self.wizard_held = false;
+            }
+        }
+        let command = match keycode {
             Keycode::Up => {
                 self.pressed_arrow = match self.pressed_arrow {
                     Arrow::None | Arrow::Up | Arrow::Down => Arrow::None,
@@ -266,7 +496,7 @@ impl EventHandler for MainState {
             Keycode::Left => {
                 let (action, pressed_arrow) = match self.pressed_arrow {
                     Arrow::Left { diagonal: false } => {
-                        (Some(Action::Walk(Direction::West)), Arrow::None)
+                        (Some(Command::Walk(Direction::West)), Arrow::None)
                     }
                     Arrow::Up => (None, Arrow::Up),
                     Arrow::Down => (None, Arrow::Down),
@@ -278,7 +508,7 @@ impl EventHandler for MainState {
             Keycode::Right => {
                 let (action, pressed_arrow) = match self.pressed_arrow {
                     Arrow::Right { diagonal: false } => {
-                        (Some(Action::Walk(Direction::East)), Arrow::None)
+                        (Some(Command::Walk(Direction::East)), Arrow::None)
                     }
                     Arrow::Up => (None, Arrow::Up),
                     Arrow::Down => (None, Arrow::Down),
@@ -289,25 +519,63 @@ impl EventHandler for MainState {
             }
             _ => None,
         };
-        if let Some(action) = action {
-            let success = match action {
-                Action::Rest => action::rest(PLAYER_ID, &mut self.world),
-                Action::Walk(direction) => action::walk(PLAYER_ID, direction, &mut self.world),
-            };
-            if success.is_ok() {
-                self.world.tick();
-            }
-            self.redraw = true;
+        if let Some(command) = command {
+            self.command(command);
         }
     }
+
+    #[cfg(feature = "wizard")]
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: i32, y: i32) {
+        if !self.wizard_held || button != MouseButton::Left {
+            return;
+        }
+        if let Some(pos) = self.point_to_pos(x as f32, y as f32) {
+            wizard::teleport(&mut self.world, pos, &mut self.log);
+            self.drain_events();
+        }
+    }
+}
+
+/// Turns to face a direction if asked to, shoves if shift is held, attacks if
+/// alt is held, or walks otherwise.
+fn direction_command(prompt: Prompt, direction: Direction, keymod: Mod) -> Command {
+    if prompt == Prompt::Turn {
+        Command::Turn(direction)
+    } else if keymod.intersects(LSHIFTMOD | RSHIFTMOD) {
+        Command::Shove(direction)
+    } else if keymod.intersects(LALTMOD | RALTMOD) {
+        Command::Attack(direction)
+    } else {
+        Command::Walk(direction)
+    }
 }
-enum Action {
-    Rest,
-    Walk(Direction),
-    // MeleeAttack(Direction),
+
+/// The inventory slot a number key picks, counting from 1 with 0 as the tenth.
+fn slot(keycode: Keycode) -> Option<usize> {
+    let index = match keycode {
+        Keycode::Num1 => 0,
+        Keycode::Num2 => 1,
+        Keycode::Num3 => 2,
+        Keycode::Num4 => 3,
+        Keycode::Num5 => 4,
+        Keycode::Num6 => 5,
+        Keycode::Num7 => 6,
+        Keycode::Num8 => 7,
+        Keycode::Num9 => 8,
+        Keycode::Num0 => 9,
+        _ => return None,
+    };
+    Some(index)
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == "--replay" {
+        if let Err(e) = play_replay(&args[2]) {
+            println!("Error in playing replay: {}", e);
+        }
+        return;
+    }
     let mut ctx = Context::load_from_conf("hex-adventure", "as-f", conf())
         .expect("Failed to load context from configuration.");
     graphics::set_default_filter(&mut ctx, graphics::FilterMode::Nearest);
@@ -316,9 +584,16 @@ fn main() {
     if let Err(e) = event::run(&mut ctx, &mut state) {
         println!("Error encountered: {}", e);
     }
-    if let Err(e) = save_world(&state.world) {
+    let saved = match state.world.status() {
+        Status::Playing => save_world(&state.world),
+        Status::Dead(_) => delete_save(),
+    };
+    if let Err(e) = saved {
         println!("Error in saving game: {}", e);
     }
+    if let Err(e) = save_replay(state.world.replay()) {
+        println!("Error in saving replay: {}", e);
+    }
 }
 
 fn conf() -> Conf {
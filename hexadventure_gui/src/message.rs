@@ -0,0 +1,244 @@
+use hexadventure::level::tile::Terrain;
+use hexadventure::prelude::*;
+use hexadventure::world::action::ActionError;
+use hexadventure::world::combat::Flank;
+use hexadventure::world::effect::Effect;
+use hexadventure::world::event::Event;
+use hexadventure::world::item::{Color, Item, Label, Potion, Scroll};
+use hexadventure::world::mob::Species;
+use hexadventure::world::status::{Cause, Death};
+
+/// The number of messages kept in the log
+const LENGTH: usize = 6;
+
+/// The most recent messages shown to the player
+pub struct Log {
+    messages: Vec<String>,
+}
+
+impl Log {
+    pub fn new() -> Self {
+        Log {
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, message: String) {
+        if self.messages.len() == LENGTH {
+            self.messages.remove(0);
+        }
+        self.messages.push(message);
+    }
+
+    /// Messages from oldest to newest
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+}
+
+pub fn describe_error(error: ActionError) -> &'static str {
+    match error {
+        ActionError::Impassable(Terrain::Wall) => "A wall blocks the way.",
+        ActionError::Impassable(Terrain::Water) => "The water is too deep.",
+        ActionError::Impassable(_) => "The way is blocked.",
+        ActionError::NotHostile => "You can't attack that.",
+        ActionError::NoTarget => "Nothing to attack.",
+        ActionError::NoStairs => "No stairs nearby.",
+        ActionError::Occupied => "Something is there.",
+        ActionError::OutOfRange => "That is out of range.",
+        ActionError::NoRangedWeapon => "No bow to shoot with.",
+        ActionError::NotThrowable => "You can't throw that.",
+        ActionError::NoItem => "There is no item.",
+        ActionError::InventoryFull => "You can't carry more.",
+        ActionError::NotEquipment => "You can't equip that.",
+        ActionError::NotConsumable => "You can't use that.",
+        ActionError::NoBush => "There is no bush here.",
+        ActionError::Dead => "You are dead.",
+    }
+}
+
+pub fn describe_event(event: &Event, world: &World) -> Option<String> {
+    match *event {
+        Event::Attacked {
+            attacker,
+            target,
+            flank,
+            guard_damage,
+            health_damage,
+        } if attacker.is_player() || target.is_player() => {
+            let damage = guard_damage + health_damage;
+            Some(match (attacker.is_player(), flank) {
+                (true, Flank::Front) => format!("You hit for {}.", damage),
+                (true, Flank::Side) => format!("You flank for {}.", damage),
+                (true, Flank::Rear) => format!("You backstab for {}.", damage),
+                (false, Flank::Front) => format!("You are hit for {}.", damage),
+                (false, Flank::Side) => format!("Flanked for {}.", damage),
+                (false, Flank::Rear) => format!("Backstabbed for {}.", damage),
+            })
+        }
+        Event::PickedUp { mob_id, item } if mob_id.is_player() => {
+            Some(format!("You take the {}.", item_name(item, world)))
+        }
+        Event::Dropped { mob_id, item } if mob_id.is_player() => {
+            Some(format!("You drop the {}.", item_name(item, world)))
+        }
+        Event::Equipped { mob_id, item } if mob_id.is_player() => {
+            Some(format!("Equipped the {}.", item_name(item, world)))
+        }
+        Event::Unequipped { mob_id, item } if mob_id.is_player() => {
+            Some(format!("Removed the {}.", item_name(item, world)))
+        }
+        Event::Harvested { mob_id, berries } if mob_id.is_player() => Some(if berries == 1 {
+            "You pick a berry.".to_owned()
+        } else {
+            format!("You pick {} berries.", berries)
+        }),
+        Event::Consumed { mob_id, item } if mob_id.is_player() => Some(match item {
+            Item::Potion(_) => "You drink the potion.".to_owned(),
+            Item::Scroll(_) => "You read the scroll.".to_owned(),
+            _ => format!("You eat the {}.", item_name(item, world)),
+        }),
+        Event::Identified(item) => Some(format!("It was {}.", a_item(item, world))),
+        Event::Teleported { mob_id, .. } if mob_id.is_player() => {
+            Some("The world shifts.".to_owned())
+        }
+        Event::Healed { mob_id, amount } if mob_id.is_player() => {
+            Some(format!("You heal {}.", amount))
+        }
+        Event::GuardRestored { mob_id, amount } if mob_id.is_player() => {
+            Some(format!("You regain {} guard.", amount))
+        }
+        Event::Blocked { attacker, .. } if attacker.is_player() => {
+            Some("Your blow is blocked.".to_owned())
+        }
+        Event::Blocked { target, .. } if target.is_player() => {
+            Some("You block the blow.".to_owned())
+        }
+        Event::Braced { mob_id } if mob_id.is_player() => Some("You brace yourself.".to_owned()),
+        Event::Shoved { attacker, .. } if attacker.is_player() => Some("You shove.".to_owned()),
+        Event::Shoved { target, .. } if target.is_player() => Some("You are shoved.".to_owned()),
+        Event::Collided { mob_id, damage } => Some(if mob_id.is_player() {
+            format!("You crash for {}.", damage)
+        } else {
+            format!("It crashes for {}.", damage)
+        }),
+        Event::GuardRecovery { mob_id, amount } if mob_id.is_player() => {
+            Some(format!("Retreat to recover {}.", amount))
+        }
+        Event::Died { mob_id, species } => Some(if mob_id.is_player() {
+            "You die...".to_owned()
+        } else {
+            format!("The {} dies.", species_name(species))
+        }),
+        Event::EffectStarted { mob_id, effect } if mob_id.is_player() => Some(
+            match effect {
+                Effect::Poison => "You are poisoned.",
+                Effect::Stun => "You are stunned.",
+                Effect::Bleeding => "You are bleeding.",
+                Effect::Slow => "You slow down.",
+                Effect::Haste => "You speed up.",
+            }
+            .to_owned(),
+        ),
+        Event::EffectEnded { mob_id, effect } if mob_id.is_player() => {
+            Some(format!("{} wore off.", effect_name(effect)))
+        }
+        Event::EffectDamage {
+            mob_id,
+            effect,
+            damage,
+        } if mob_id.is_player() => Some(format!("{} hurts for {}.", effect_name(effect), damage)),
+        Event::Descended => Some("You descend.".to_owned()),
+        Event::Ascended => Some("You ascend.".to_owned()),
+        _ => None,
+    }
+}
+
+pub fn describe_death(death: Death) -> String {
+    match (death.cause, death.killer) {
+        (Cause::Melee, Some(killer)) => format!("Killed by a {}.", species_name(killer)),
+        (Cause::Melee, None) => "You were killed.".to_owned(),
+        (Cause::Ranged, Some(killer)) => format!("Shot by a {}.", species_name(killer)),
+        (Cause::Ranged, None) => "You were shot.".to_owned(),
+        (Cause::Collision, _) => "Died in a collision.".to_owned(),
+        (Cause::Drowned, _) => "You drowned.".to_owned(),
+        (Cause::Wizard, _) => "Smitten by a wizard.".to_owned(),
+        (Cause::Effect(Effect::Bleeding), Some(killer)) => {
+            format!("Bled out by a {}.", species_name(killer))
+        }
+        (Cause::Effect(Effect::Bleeding), _) => "Bled to death.".to_owned(),
+        (Cause::Effect(Effect::Poison), Some(killer)) => {
+            format!("Poisoned by a {}.", species_name(killer))
+        }
+        (Cause::Effect(effect), _) => format!("Died of {}.", effect_name(effect).to_lowercase()),
+    }
+}
+
+fn species_name(species: Species) -> &'static str {
+    match species {
+        Species::Hero => "hero",
+        Species::Skeleton => "skeleton",
+    }
+}
+
+/// The name of an item, or of how it looks if the player hasn't identified it
+pub fn item_name(item: Item, world: &World) -> String {
+    let name = match item {
+        Item::Spear => "spear",
+        Item::Axe => "axe",
+        Item::Rapier => "rapier",
+        Item::Dagger => "dagger",
+        Item::Bow => "bow",
+        Item::Javelin => "javelin",
+        Item::Jerkin => "jerkin",
+        Item::Cuirass => "cuirass",
+        Item::Shield => "shield",
+        Item::Brownberry => "berry",
+        Item::Potion(potion) if !world.is_identified(item) => {
+            let color = match world.appearances().color(potion) {
+                Color::Red => "red",
+                Color::Blue => "blue",
+                Color::Green => "green",
+                Color::Gray => "gray",
+            };
+            return format!("{} vial", color);
+        }
+        Item::Scroll(scroll) if !world.is_identified(item) => {
+            let label = match world.appearances().label(scroll) {
+                Label::Xam => "XAM",
+                Label::Zot => "ZOT",
+                Label::Pleh => "PLEH",
+                Label::Ruk => "RUK",
+                Label::Vox => "VOX",
+            };
+            return format!("{} scroll", label);
+        }
+        Item::Potion(Potion::Healing) => "tonic",
+        Item::Potion(Potion::Fortitude) => "elixir",
+        Item::Potion(Potion::Swiftness) => "quicksilver",
+        Item::Scroll(Scroll::Teleportation) => "warp scroll",
+        Item::Scroll(Scroll::Mapping) => "map scroll",
+        Item::Scroll(Scroll::Blinking) => "dash scroll",
+    };
+    name.to_owned()
+}
+
+/// The name of an item after "a" or "an"
+pub fn a_item(item: Item, world: &World) -> String {
+    let name = item_name(item, world);
+    if name.starts_with(|c| "aeiou".contains(c)) {
+        format!("an {}", name)
+    } else {
+        format!("a {}", name)
+    }
+}
+
+pub fn effect_name(effect: Effect) -> &'static str {
+    match effect {
+        Effect::Poison => "Poison",
+        Effect::Stun => "Stun",
+        Effect::Bleeding => "Bleeding",
+        Effect::Slow => "Slow",
+        Effect::Haste => "Haste",
+    }
+}
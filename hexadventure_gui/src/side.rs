@@ -4,6 +4,7 @@ use ggez::{Context, GameResult};
 use grid;
 use hexadventure::prelude::*;
 use hexadventure::world::mob;
+use message::{effect_name, item_name};
 
 pub const WIDTH: u32 = 24;
 
@@ -19,6 +20,7 @@ impl Sidebar {
         ctx: &mut Context,
         dest: Point2,
         world: &World,
+        messages: &[String],
         spritebatch: &mut SpriteBatch,
     ) -> GameResult<()> {
         let width = WIDTH as f32 * 9.0;
@@ -38,6 +40,11 @@ impl Sidebar {
                 ..Default::default()
             },
         )?;
+        draw_str(
+            &format!("Depth: {}", world.depth()),
+            spritebatch,
+            Point2::new(dest.x + 18.0, dest.y),
+        )?;
         draw_str(
             &format!("Health: {}", world.player.health),
             spritebatch,
@@ -48,21 +55,70 @@ impl Sidebar {
             spritebatch,
             Point2::new(dest.x + 18.0, dest.y + 32.0),
         )?;
+        for (index, active) in world.player.effects.iter().enumerate() {
+            draw_str(
+                &format!("{}: {}", effect_name(active.effect), active.turns),
+                spritebatch,
+                Point2::new(dest.x + 18.0, dest.y + 48.0 + 16.0 * index as f32),
+            )?;
+        }
+        let top = dest.y + 48.0 + 16.0 * world.player.effects.len() as f32;
+        if let Some(item) = world.level[world.player.pos].item {
+            draw_str(
+                &format!("Here: {}", item_name(item, world)),
+                spritebatch,
+                Point2::new(dest.x + 18.0, top),
+            )?;
+        }
+        let top = top + 16.0;
+        for (index, &item) in world.player.inventory.iter().enumerate() {
+            draw_str(
+                &format!("{}: {}", (index + 1) % 10, item_name(item, world)),
+                spritebatch,
+                Point2::new(dest.x + 18.0, top + 16.0 * index as f32),
+            )?;
+        }
+        let top = top + 16.0 * world.player.inventory.len() as f32;
+        let equipment = &world.player.equipment;
+        let slots = [
+            ("Weapon", equipment.weapon),
+            ("Armor", equipment.armor),
+            ("Shield", equipment.shield),
+        ];
+        for (index, &(slot, item)) in slots.iter().enumerate() {
+            draw_str(
+                &format!(
+                    "{}: {}",
+                    slot,
+                    item.map_or("-".to_owned(), |item| item_name(item, world))
+                ),
+                spritebatch,
+                Point2::new(dest.x + 18.0, top + 16.0 * index as f32),
+            )?;
+        }
+        let top = top + 16.0 * slots.len() as f32;
         let mut i = 0;
         mob::for_each(world, |mob_id| {
             let mob = &world[mob_id];
             draw_str(
                 &format!("Health: {}", mob.health),
                 spritebatch,
-                Point2::new(dest.x + 18.0, dest.y + 48.0 + 32.0 * i as f32),
+                Point2::new(dest.x + 18.0, top + 32.0 * i as f32),
             );
             draw_str(
                 &format!("Guard: {}", mob.guard),
                 spritebatch,
-                Point2::new(dest.x + 18.0, dest.y + 64.0 + 32.0 * i as f32),
+                Point2::new(dest.x + 18.0, top + 16.0 + 32.0 * i as f32),
             );
             i += 1;
         });
+        for (index, message) in messages.iter().rev().enumerate() {
+            draw_str(
+                message,
+                spritebatch,
+                Point2::new(dest.x + 18.0, dest.y + height - 32.0 - 16.0 * index as f32),
+            )?;
+        }
         // for (index, mob) in game.mobs.npcs.iter().enumerate() {
         //     draw_str(&format!("Guard: {}", mob.guard), spritebatch, Point2::new(dest.x + 18.0, dest.y + 32.0 + 16.0 * index as f32))?;
         // }
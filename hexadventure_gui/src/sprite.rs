@@ -7,6 +7,7 @@ use image::GenericImage;
 use image::ImageFormat;
 
 use hexadventure::level::tile::Terrain;
+use hexadventure::world::item::{self, Appearances, Item};
 use hexadventure::world::mob::Species;
 
 pub enum Sprite {
@@ -20,6 +21,18 @@ pub enum Sprite {
     Exit,
     Water,
     Skeleton,
+    Spear,
+    Axe,
+    Rapier,
+    Dagger,
+    Bow,
+    Javelin,
+    Jerkin,
+    Cuirass,
+    Shield,
+    Berry,
+    Potion,
+    Scroll,
 }
 
 impl From<Terrain> for Sprite {
@@ -44,6 +57,42 @@ pub fn sprite_from_species(species: &Species) -> Sprite {
     }
 }
 
+pub fn sprite_from_item(item: Item) -> Sprite {
+    match item {
+        Item::Spear => Sprite::Spear,
+        Item::Axe => Sprite::Axe,
+        Item::Rapier => Sprite::Rapier,
+        Item::Dagger => Sprite::Dagger,
+        Item::Bow => Sprite::Bow,
+        Item::Javelin => Sprite::Javelin,
+        Item::Jerkin => Sprite::Jerkin,
+        Item::Cuirass => Sprite::Cuirass,
+        Item::Shield => Sprite::Shield,
+        Item::Brownberry => Sprite::Berry,
+        Item::Potion(_) => Sprite::Potion,
+        Item::Scroll(_) => Sprite::Scroll,
+    }
+}
+
+/// The color an item is drawn in, which for a potion gives away its appearance
+pub fn color_from_item(item: Item, appearances: &Appearances) -> Color {
+    match item {
+        Item::Spear | Item::Axe | Item::Rapier | Item::Dagger | Item::Javelin => {
+            Color::new(0.75, 0.75, 1.0, 1.0)
+        }
+        Item::Jerkin | Item::Bow => Color::new(0.6, 0.4, 0.2, 1.0),
+        Item::Cuirass | Item::Shield => Color::new(0.75, 0.75, 0.75, 1.0),
+        Item::Brownberry => Color::new(0.6, 0.3, 0.1, 1.0),
+        Item::Potion(potion) => match appearances.color(potion) {
+            item::Color::Red => Color::new(1.0, 0.2, 0.2, 1.0),
+            item::Color::Blue => Color::new(0.3, 0.3, 1.0, 1.0),
+            item::Color::Green => Color::new(0.2, 0.9, 0.2, 1.0),
+            item::Color::Gray => Color::new(0.6, 0.6, 0.6, 1.0),
+        },
+        Item::Scroll(_) => Color::new(0.9, 0.85, 0.7, 1.0),
+    }
+}
+
 pub fn color_from_tile(terrain: Terrain) -> Color {
     use self::Terrain::*;
     match terrain {
@@ -73,7 +122,8 @@ pub fn load_spritebatch(ctx: &mut Context) -> SpriteBatch {
         width as u16,
         height as u16,
         &dynamic_image.raw_pixels(),
-    ).expect("Failed to parse image.");
+    )
+    .expect("Failed to parse image.");
     SpriteBatch::new(ggez_image)
 }
 
@@ -92,6 +142,18 @@ pub fn sprite_src(sprite: Sprite) -> Rect {
         Water => (5, 0),
         Player => (0, 1),
         Skeleton => (3, 1),
+        Spear => (0, 2),
+        Axe => (1, 2),
+        Rapier => (2, 2),
+        Jerkin => (3, 2),
+        Cuirass => (4, 2),
+        Shield => (5, 2),
+        Berry => (6, 2),
+        Potion => (7, 2),
+        Scroll => (8, 2),
+        Bow => (9, 2),
+        Javelin => (10, 2),
+        Dagger => (11, 2),
     };
     let w = 16;
     let h = 24;
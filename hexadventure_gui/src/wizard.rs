@@ -0,0 +1,85 @@
+//! Wizard mode commands, bound to the control key.
+
+use ggez::event::{Keycode, Mod, LCTRLMOD, RCTRLMOD};
+use hexadventure::prelude::*;
+use hexadventure::world::action::ActionError;
+use hexadventure::world::faction::Faction;
+use hexadventure::world::mob::{Species, PLAYER_ID};
+use message::{a_item, describe_error, Log};
+
+pub fn is_wizard_mod(keymod: Mod) -> bool {
+    keymod.intersects(LCTRLMOD | RCTRLMOD)
+}
+
+pub fn is_wizard_key(keycode: Keycode) -> bool {
+    keycode == Keycode::LCtrl || keycode == Keycode::RCtrl
+}
+
+/// Runs the wizard command bound to a key and returns whether there was one.
+pub fn key_down(world: &mut World, keycode: Keycode, log: &mut Log) -> bool {
+    match keycode {
+        Keycode::R => {
+            world.wizard().reveal_level();
+            log.push("Level revealed.".to_owned());
+        }
+        Keycode::S => {
+            let pos = world.player.pos + world.player.facing;
+            match world.wizard().spawn(Species::Skeleton, pos) {
+                Ok(_) => log.push("Skeleton spawned.".to_owned()),
+                Err(error) => log.push(describe_error(error).to_owned()),
+            }
+        }
+        Keycode::C => {
+            let pos = world.player.pos + world.player.facing;
+            match world.level[pos].mob_id {
+                Some(mob_id) => {
+                    world.wizard().set_faction(mob_id, Faction::Allied);
+                    log.push("Charmed.".to_owned());
+                }
+                None => log.push(describe_error(ActionError::NoTarget).to_owned()),
+            }
+        }
+        Keycode::G => {
+            let pos = world.player.pos;
+            match world.wizard().create_random_item(pos) {
+                Some((item, _)) => {
+                    let message = format!("Created {}.", a_item(item, world));
+                    log.push(message);
+                }
+                None => log.push("There is no room.".to_owned()),
+            }
+        }
+        Keycode::H => {
+            let (max_health, max_guard) = (world.player.max_health, world.player.max_guard);
+            world.wizard().set_health(PLAYER_ID, max_health);
+            world.wizard().set_guard(PLAYER_ID, max_guard);
+            log.push("Health and guard full.".to_owned());
+        }
+        Keycode::N => {
+            let depth = world.depth() + 1;
+            world.wizard().jump_to_depth(depth);
+            log.push(format!("Jumped to depth {}.", depth));
+        }
+        Keycode::P => {
+            let depth = world.depth() - 1;
+            world.wizard().jump_to_depth(depth);
+            log.push(format!("Jumped to depth {}.", world.depth()));
+        }
+        Keycode::I => {
+            if world.wizard().toggle_invulnerable() {
+                log.push("You are invulnerable.".to_owned());
+            } else {
+                log.push("You are vulnerable.".to_owned());
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+pub fn teleport(world: &mut World, pos: Pos, log: &mut Log) {
+    match world.wizard().teleport(pos) {
+        Ok(()) => log.push("Teleported.".to_owned()),
+        Err(error) => log.push(describe_error(error).to_owned()),
+    }
+}